@@ -1,231 +1,2432 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
-    StdResult, Uint128,
+    from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Empty,
+    Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128, WasmMsg,
 };
-//use cw2::set_contract_version;
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, MessagesResponse, QueryMsg, LikesResponse};
-use crate::state::{Like, Message, CURRENT_ID, MESSAGES, MESSAGES_LIKE, STIPEND};
+use crate::migrations;
+use crate::trending::{add_like, current_score, remove_like};
+use cw_storage_plus::{Bound, Map, PrimaryKey};
+
+use crate::msg::{
+    AuthorStatsResponse, BansResponse, ConfigResponse, Cw20PriceMsg, ExecuteMsg, InstantiateMsg,
+    LikersResponse, LikesResponse, MessageHistoryResponse, MessagesResponse, MigrateMsg,
+    ModerationQueueResponse, ModeratorsResponse, OrderBy, QueryMsg, RankedMessage,
+    RateLimitQuotaResponse, ReactionCount, ReactionsResponse, ReceiveMsg, Report, ReportedMessage,
+    ReportsResponse, SponsoredQuotaResponse, StatsResponse, TagCount, TagMatch, TagsResponse,
+    TopAuthorsResponse, TopMessagesResponse, TopicResponse, TopicsResponse, TreasuryResponse,
+    TrendingMessage, TrendingResponse, UpdateConfigMsg,
+};
+use crate::state::{
+    created_nanos, message_likes, messages, rank_likes, rank_trending, update_author_stats,
+    update_topic_counts, Ban, Config, ContentRules, Cw20Price, Like, Message, MessageLike,
+    RateLimit, RateWindow, Reaction, Stats, Topic, TopicCreation, AUTHOR_RANKING, AUTHOR_STATS,
+    BANS, CONFIG, CURRENT_ID, DEFAULT_MAX_THREAD_DEPTH, DEFAULT_TRENDING_HALF_LIFE, FEES,
+    FEES_COLLECTED, LIKE_RANKING, MESSAGES_LIKE, MESSAGE_HISTORY, MODERATION_QUEUE, MODERATORS,
+    RATE_WINDOWS, REACTION_COUNTS, REPLIES, REPORTS, SPONSORED_POSTS, STATS, TAGGED, TAG_COUNTS,
+    TOPICS, TOPIC_COUNTS, TOPIC_LIKE_RANKING, TOPIC_TRENDING_RANKING, TRENDING, TRENDING_RANKING,
+};
 
 // version info for migration info
-//const CONTRACT_NAME: &str = "crates.io:messages";
-//const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const CONTRACT_NAME: &str = "crates.io:messages";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_FEE_BPS: u16 = 10_000;
+const CW20_DENOM_PREFIX: &str = "cw20:";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CURRENT_ID.save(deps.storage, &Uint128::zero().u128())?;
-    let required_coin = msg.stipend;
+    validate_stipend(&msg.post_price)?;
+    validate_stipend(&msg.like_price)?;
+    let fee_bps = msg.fee_bps.unwrap_or_default();
+    validate_fee_bps(fee_bps)?;
+    let treasury = msg
+        .treasury
+        .map(|treasury| deps.api.addr_validate(&treasury))
+        .transpose()?;
+    let reactions = msg.reactions.unwrap_or_default();
+    validate_reactions(&reactions)?;
+    let topic_price = msg.topic_price.unwrap_or_default();
+    validate_topic_price(&topic_price)?;
+    let trending_half_life = msg.trending_half_life.unwrap_or(DEFAULT_TRENDING_HALF_LIFE);
+    validate_half_life(trending_half_life)?;
+    let content_rules = msg.content_rules.unwrap_or_default();
+    validate_content_rules(&content_rules)?;
+    let cw20 = msg
+        .cw20
+        .map(|price| validate_cw20_price(deps.api, price))
+        .transpose()?;
+
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            admin,
+            post_price: msg.post_price,
+            like_price: msg.like_price,
+            fee_bps,
+            treasury,
+            max_thread_depth: msg.max_thread_depth.unwrap_or(DEFAULT_MAX_THREAD_DEPTH),
+            reactions,
+            cw20,
+            strict_topics: msg.strict_topics.unwrap_or_default(),
+            topic_creation: msg.topic_creation.unwrap_or_default(),
+            topic_price,
+            auto_hide_reports: msg.auto_hide_reports.unwrap_or_default(),
+            rate_limit: msg.rate_limit.filter(|rate_limit| rate_limit.window_blocks > 0),
+            trending_half_life,
+            content_rules,
+        },
+    )?;
+
+    Ok(Response::default())
+}
 
-    // A stipend needs to be provided by user. Named coin, greater than 1.
-    if required_coin.denom == "" || required_coin.amount < Uint128::from(1u128) {
+// A price needs to be provided by user. At least one coin, one per denom.
+fn validate_stipend(stipend: &[Coin]) -> Result<(), ContractError> {
+    if stipend.is_empty() {
         return Err(ContractError::ValidCoinRequired {});
     }
-    STIPEND.save(deps.storage, &required_coin)?;
+    for (i, coin) in stipend.iter().enumerate() {
+        validate_coin(coin)?;
+        if stipend[..i].iter().any(|c| c.denom == coin.denom) {
+            return Err(ContractError::DuplicateDenom {
+                denom: coin.denom.clone(),
+            });
+        }
+    }
+    Ok(())
+}
 
-    Ok(Response::default())
+// The fee is a share of the payment, so it can't go above 100%.
+fn validate_fee_bps(fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(ContractError::InvalidFeeBps { fee_bps });
+    }
+    Ok(())
+}
+
+fn validate_half_life(half_life: u64) -> Result<(), ContractError> {
+    if half_life == 0 {
+        return Err(ContractError::InvalidHalfLife {});
+    }
+    Ok(())
+}
+
+fn validate_content_rules(rules: &ContentRules) -> Result<(), ContractError> {
+    if rules.min_message_length > rules.max_message_length
+        || rules.max_message_length == 0
+        || rules.max_topic_length == 0
+        || rules.max_title_length == 0
+    {
+        return Err(ContractError::InvalidContentRules {});
+    }
+    Ok(())
+}
+
+// Registering topics can be free, otherwise the price is checked like the others.
+fn validate_topic_price(price: &[Coin]) -> Result<(), ContractError> {
+    if price.is_empty() {
+        return Ok(());
+    }
+    validate_stipend(price)
+}
+
+fn validate_cw20_price(api: &dyn Api, price: Cw20PriceMsg) -> Result<Cw20Price, ContractError> {
+    if price.post_amount.is_zero() || price.like_amount.is_zero() {
+        return Err(ContractError::ValidCoinRequired {});
+    }
+    Ok(Cw20Price {
+        address: api.addr_validate(&price.address)?,
+        post_amount: price.post_amount,
+        like_amount: price.like_amount,
+    })
+}
+
+// Named coin, greater than 1. Native denoms can't pass for a CW20 token, which is
+// paid, booked and sent under `cw20:<token address>`.
+fn validate_coin(coin: &Coin) -> Result<(), ContractError> {
+    if coin.denom.is_empty() || coin.amount < Uint128::from(1u128) {
+        return Err(ContractError::ValidCoinRequired {});
+    }
+    if coin.denom.starts_with(CW20_DENOM_PREFIX) {
+        return Err(ContractError::ReservedDenom {
+            denom: coin.denom.clone(),
+        });
+    }
+    Ok(())
+}
+
+// Returns the coin paid when the sender sent exactly one coin matching one of the prices.
+fn paid_coin(info: &MessageInfo, prices: &[Coin]) -> Option<Coin> {
+    match info.funds.as_slice() {
+        [paid] if prices.contains(paid) => Some(paid.clone()),
+        _ => None,
+    }
+}
+
+// The admin can do anything a moderator can.
+fn check_moderator(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if *sender != CONFIG.load(storage)?.admin && !MODERATORS.has(storage, sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+// Bans that have run out are cleared the next time the address acts.
+fn check_not_banned(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+) -> Result<(), ContractError> {
+    if let Some(ban) = BANS.may_load(storage, address)? {
+        match ban.until_height {
+            Some(until_height) if until_height <= env.block.height => {
+                BANS.remove(storage, address)
+            }
+            until_height => return Err(ContractError::Banned { until_height }),
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy)]
+enum RateAction {
+    Post,
+    Like,
+}
+
+// Returns the address's current window, or a fresh one when the last window is over.
+fn rate_window(
+    storage: &dyn Storage,
+    env: &Env,
+    address: &Addr,
+    rate_limit: &RateLimit,
+) -> StdResult<RateWindow> {
+    let window = match RATE_WINDOWS.may_load(storage, address)? {
+        Some(window) if env.block.height < window_end(&window, rate_limit) => window,
+        _ => RateWindow {
+            start_height: env.block.height,
+            ..RateWindow::default()
+        },
+    };
+    Ok(window)
+}
+
+fn window_end(window: &RateWindow, rate_limit: &RateLimit) -> u64 {
+    window.start_height.saturating_add(rate_limit.window_blocks)
+}
+
+// Counts the action against the address's rate limit, if there is one.
+fn check_rate_limit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    address: &Addr,
+    action: RateAction,
+) -> Result<(), ContractError> {
+    let rate_limit = match CONFIG.load(storage)?.rate_limit {
+        Some(rate_limit) => rate_limit,
+        None => return Ok(()),
+    };
+    let mut window = rate_window(storage, env, address, &rate_limit)?;
+    let (used, limit, name) = match action {
+        RateAction::Post => (&mut window.posts, rate_limit.max_posts, "posts"),
+        RateAction::Like => (&mut window.likes, rate_limit.max_likes, "likes"),
+    };
+    if limit > 0 && *used >= limit {
+        return Err(ContractError::RateLimited {
+            action: name.to_string(),
+            limit,
+            window_blocks: rate_limit.window_blocks,
+            reset_height: window_end(&window, &rate_limit),
+        });
+    }
+    *used += 1;
+    RATE_WINDOWS.save(storage, address, &window)?;
+    Ok(())
+}
+
+// In strict mode messages can only use registered topics.
+// Topics are stored and looked up trimmed and lowercased, so "News " and "news" are the same.
+pub(crate) fn normalize_topic(topic: &str) -> String {
+    topic.trim().to_lowercase()
+}
+
+// Checks a normalized topic against the content rules.
+fn check_topic_content(rules: &ContentRules, topic: &str) -> Result<(), ContractError> {
+    if topic.is_empty() {
+        return Err(ContractError::EmptyTopic {});
+    }
+    if topic.chars().count() > rules.max_topic_length as usize {
+        return Err(ContractError::TopicTooLong {
+            max_length: rules.max_topic_length,
+        });
+    }
+    if rules.topic_charset.is_empty() {
+        return Ok(());
+    }
+    match topic.chars().find(|c| !rules.topic_charset.contains(*c)) {
+        Some(character) => Err(ContractError::InvalidTopicCharacter { character }),
+        None => Ok(()),
+    }
+}
+
+// Normalizes tags like topics and checks them against the same rules.
+fn normalize_tags(rules: &ContentRules, tags: Vec<String>) -> Result<Vec<String>, ContractError> {
+    if tags.len() > rules.max_tags as usize {
+        return Err(ContractError::TooManyTags {
+            max_tags: rules.max_tags,
+        });
+    }
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = normalize_topic(&tag);
+        check_topic_content(rules, &tag).map_err(|err| match err {
+            ContractError::EmptyTopic {} => ContractError::EmptyTag {},
+            ContractError::TopicTooLong { max_length } => ContractError::TagTooLong { max_length },
+            ContractError::InvalidTopicCharacter { character } => {
+                ContractError::InvalidTagCharacter { character }
+            }
+            err => err,
+        })?;
+        if normalized.contains(&tag) {
+            return Err(ContractError::DuplicateTag { tag });
+        }
+        normalized.push(tag);
+    }
+    Ok(normalized)
+}
+
+fn check_message_content(rules: &ContentRules, message: &str) -> Result<(), ContractError> {
+    if message.trim().chars().count() < rules.min_message_length as usize {
+        return Err(ContractError::MessageTooShort {
+            min_length: rules.min_message_length,
+        });
+    }
+    if message.chars().count() > rules.max_message_length as usize {
+        return Err(ContractError::MessageTooLong {
+            max_length: rules.max_message_length,
+        });
+    }
+    Ok(())
+}
+
+// Checks the title and description of a topic being registered.
+fn check_topic_details(rules: &ContentRules, title: &str, description: &str) -> Result<(), ContractError> {
+    if title.chars().count() > rules.max_title_length as usize {
+        return Err(ContractError::TitleTooLong {
+            max_length: rules.max_title_length,
+        });
+    }
+    if description.chars().count() > rules.max_message_length as usize {
+        return Err(ContractError::DescriptionTooLong {
+            max_length: rules.max_message_length,
+        });
+    }
+    Ok(())
+}
+
+fn check_topic(storage: &dyn Storage, topic: &str) -> Result<(), ContractError> {
+    if CONFIG.load(storage)?.strict_topics && !TOPICS.has(storage, topic) {
+        return Err(ContractError::UnknownTopic {
+            topic: topic.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn update_stats(
+    storage: &mut dyn Storage,
+    update: impl FnOnce(&mut Stats) -> StdResult<()>,
+) -> StdResult<()> {
+    let mut stats = STATS.may_load(storage)?.unwrap_or_default();
+    update(&mut stats)?;
+    STATS.save(storage, &stats)
+}
+
+// Counts a payment made to the contract in the payer's and the board's totals.
+fn record_payment(storage: &mut dyn Storage, payer: &Addr, paid: &Coin) -> StdResult<()> {
+    update_author_stats(storage, payer, |stats| add_coin(&mut stats.spent, paid))?;
+    update_stats(storage, |stats| add_coin(&mut stats.payment_volume, paid))
+}
+
+// Books a payment kept by the contract, so it can be withdrawn later.
+fn record_fee(storage: &mut dyn Storage, fee: &Coin) -> StdResult<()> {
+    if fee.amount.is_zero() {
+        return Ok(());
+    }
+    FEES.update(storage, &fee.denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_add(fee.amount)?)
+    })?;
+    FEES_COLLECTED.update(storage, &fee.denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_add(fee.amount)?)
+    })?;
+    Ok(())
+}
+
+fn protocol_fee(config: &Config, paid: &Coin) -> Uint128 {
+    paid.amount.multiply_ratio(config.fee_bps, MAX_FEE_BPS)
+}
+
+// Gives back a fee booked by `record_fee`, or what the withdrawals left of it.
+fn refund_fee(storage: &mut dyn Storage, fee: &Coin) -> StdResult<Coin> {
+    let available = FEES.may_load(storage, &fee.denom)?.unwrap_or_default();
+    let refund = Coin::new(fee.amount.min(available).u128(), &fee.denom);
+    if refund.amount.is_zero() {
+        return Ok(refund);
+    }
+    FEES.save(storage, &fee.denom, &(available - refund.amount))?;
+    FEES_COLLECTED.update(storage, &fee.denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_sub(refund.amount)?)
+    })?;
+    Ok(refund)
+}
+
+// Splits a payment between the author and the protocol fee. The fee goes to the treasury
+// when one is set, otherwise it stays in the contract.
+fn pay_author(
+    storage: &mut dyn Storage,
+    response: Response,
+    config: &Config,
+    author: &Addr,
+    paid: Coin,
+) -> StdResult<Response> {
+    let fee = protocol_fee(config, &paid);
+    let author_share = Coin::new((paid.amount - fee).u128(), &paid.denom);
+    let fee_share = Coin::new(fee.u128(), &paid.denom);
+    update_author_stats(storage, author, |stats| add_coin(&mut stats.earned, &author_share))?;
+
+    let mut response = response
+        .add_attribute("sent_to", author.to_string())
+        .add_attribute("author_share", author_share.to_string())
+        .add_attribute("fee_share", fee_share.to_string());
+    if !author_share.amount.is_zero() {
+        response = response.add_message(send_coin(author, author_share)?);
+    }
+    match &config.treasury {
+        Some(treasury) if !fee_share.amount.is_zero() => {
+            response = response.add_message(send_coin(treasury, fee_share)?);
+        }
+        Some(_) => {}
+        None => record_fee(storage, &fee_share)?,
+    }
+    Ok(response)
+}
+
+// CW20 payments are handled as coins of this denom, so prices, fees and payouts work the
+// same way for both kinds of token.
+fn cw20_denom(address: &Addr) -> String {
+    format!("{}{}", CW20_DENOM_PREFIX, address)
+}
+
+// Sends a native coin with the bank module, or a CW20 one with a transfer on its contract.
+fn send_coin(to: &Addr, coin: Coin) -> StdResult<CosmosMsg> {
+    let msg = match coin.denom.strip_prefix(CW20_DENOM_PREFIX) {
+        Some(token) => WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: to.to_string(),
+                amount: coin.amount,
+            })?,
+            funds: vec![],
+        }
+        .into(),
+        None => BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![coin],
+        }
+        .into(),
+    };
+    Ok(msg)
+}
+
+// Adds to the coin of the same denom, or appends a new one.
+fn add_coin(coins: &mut Vec<Coin>, coin: &Coin) -> StdResult<()> {
+    match coins.iter_mut().find(|c| c.denom == coin.denom) {
+        Some(c) => c.amount = c.amount.checked_add(coin.amount)?,
+        None if !coin.amount.is_zero() => coins.push(coin.clone()),
+        None => {}
+    }
+    Ok(())
+}
+
+fn format_coins(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(Coin::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn validate_reactions(reactions: &[Reaction]) -> Result<(), ContractError> {
+    for (i, reaction) in reactions.iter().enumerate() {
+        if reaction.name.is_empty() || reactions[..i].iter().any(|r| r.name == reaction.name) {
+            return Err(ContractError::InvalidReactionName {
+                reaction: reaction.name.clone(),
+            });
+        }
+        validate_coin(&reaction.price)?;
+    }
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::AddMessage { topic, message } => add_message(deps, info, topic, message),
-        ExecuteMsg::AddMessageWithoutFunds { topic, message } => add_message_without_funds(deps, info, topic, message),
-        ExecuteMsg::LikeMessage { id } => { like_message( deps, info, id) }
+        ExecuteMsg::AddMessage {
+            topic,
+            message,
+            parent_id,
+            tags,
+        } => add_message(deps, env, info, topic, message, parent_id, tags),
+        ExecuteMsg::AddMessageWithoutFunds {
+            topic,
+            message,
+            tags,
+        } => add_message_without_funds(deps, env, info, topic, message, tags),
+        ExecuteMsg::LikeMessage { id } => { like_message( deps, env, info, id) }
+        ExecuteMsg::UnlikeMessage { id } => unlike_message(deps, info, id),
+        ExecuteMsg::React { id, reaction } => react(deps, env, info, id, reaction),
+        ExecuteMsg::DeleteMessage { id } => delete_message(deps, info, id),
+        ExecuteMsg::EditMessage { id, message, topic } => {
+            edit_message(deps, env, info, id, message, topic)
+        }
+        ExecuteMsg::UpdateConfig(msg) => update_config(deps, info, msg),
+        ExecuteMsg::ReportMessage { id, reason } => report_message(deps, info, id, reason),
+        ExecuteMsg::UpdateModerators { add, remove } => {
+            update_moderators(deps, info, add, remove)
+        }
+        ExecuteMsg::HideMessage { id } => moderate_message(deps, info, id, true),
+        ExecuteMsg::RestoreMessage { id } => moderate_message(deps, info, id, false),
+        ExecuteMsg::Ban {
+            address,
+            until_height,
+        } => ban(deps, info, address, until_height),
+        ExecuteMsg::Unban { address } => unban(deps, info, address),
+        ExecuteMsg::CreateTopic {
+            name,
+            title,
+            description,
+        } => create_topic(deps, info, name, title, description),
+        ExecuteMsg::GrantSponsoredPosts { address, quota } => {
+            grant_sponsored_posts(deps, info, address, quota)
+        }
+        ExecuteMsg::WithdrawFees { to, amount } => withdraw_fees(deps, info, to, amount),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
     }
 }
 
 pub fn add_message(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     topic: String,
     message: String,
+    parent_id: Option<Uint128>,
+    tags: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
-    //load current id
-    let mut current_id = CURRENT_ID.load(deps.storage)?;
-    
     // Making sure the user has sent the funds to create the message
-    let stipend = STIPEND.load(deps.storage)?;
-    // Need to ask in which order are the comparisons evaluated
-    if info.funds.len() != 1
-        || info.funds[0].denom != stipend.denom
-        || info.funds[0].amount != stipend.amount
-    {
-        return Err(ContractError::InvalidFundsMessage {
-            val1: stipend.denom,
-            val2: stipend.amount.to_string(),
-        });
-    }
- 
-    //create new message
-    let new_message = Message {
-        id: Uint128::from(current_id),
-        owner: info.sender,
-        topic: topic,
-        message: message,
+    let post_price = CONFIG.load(deps.storage)?.post_price;
+    let paid = match paid_coin(&info, &post_price) {
+        Some(paid) => paid,
+        None => {
+            return Err(ContractError::InvalidFundsMessage {
+                accepted: format_coins(&post_price),
+            })
+        }
+    };
+    let post = NewPost {
+        topic,
+        message,
+        parent_id,
+        tags: tags.unwrap_or_default(),
     };
+    add_paid_message(deps, &env, info.sender, paid, post)
+}
 
-    //increment current id
-    current_id = current_id.checked_add(1).unwrap();
+// What is posted, whichever way the message is paid for
+struct NewPost {
+    topic: String,
+    message: String,
+    parent_id: Option<Uint128>,
+    tags: Vec<String>,
+}
 
-    MESSAGES.save(deps.storage, new_message.id.u128(), &new_message)?;
+fn add_paid_message(
+    deps: DepsMut,
+    env: &Env,
+    owner: Addr,
+    paid: Coin,
+    post: NewPost,
+) -> Result<Response, ContractError> {
+    // The whole post price is kept as a fee
+    record_fee(deps.storage, &paid)?;
+    record_payment(deps.storage, &owner, &paid)?;
 
-    //save current id
-    CURRENT_ID.save(deps.storage, &current_id)?;
+    let new_message = save_new_message(deps, env, owner, post)?;
 
     Ok(Response::new()
         .add_attribute("action", "add_message")
         .add_attribute("id", new_message.id.to_string()))
 }
 
-// This ExecuteMsg has been implementing for testing purposes, as the 
+// Free posts, each one uses up one of the sender's sponsored posts.
 pub fn add_message_without_funds(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     topic: String,
     message: String,
+    tags: Option<Vec<String>>,
 ) -> Result<Response, ContractError> {
+    match SPONSORED_POSTS.may_load(deps.storage, &info.sender)? {
+        Some(1) => SPONSORED_POSTS.remove(deps.storage, &info.sender),
+        Some(quota) => SPONSORED_POSTS.save(deps.storage, &info.sender, &(quota - 1))?,
+        None => return Err(ContractError::NotSponsored {}),
+    }
+    let post = NewPost {
+        topic,
+        message,
+        parent_id: None,
+        tags: tags.unwrap_or_default(),
+    };
+    let new_message = save_new_message(deps, &env, info.sender, post)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_message_without_funds")
+        .add_attribute("id", new_message.id.to_string()))
+}
+
+// Stores a new message under the next id. Replies are checked against their parent,
+// which gets its reply count bumped.
+fn save_new_message(
+    deps: DepsMut,
+    env: &Env,
+    owner: Addr,
+    post: NewPost,
+) -> Result<Message, ContractError> {
+    let NewPost {
+        topic,
+        message,
+        parent_id,
+        tags,
+    } = post;
+    check_not_banned(deps.storage, env, &owner)?;
+    check_rate_limit(deps.storage, env, &owner, RateAction::Post)?;
+    let rules = CONFIG.load(deps.storage)?.content_rules;
+    let topic = normalize_topic(&topic);
+    check_topic_content(&rules, &topic)?;
+    check_message_content(&rules, &message)?;
+    let tags = normalize_tags(&rules, tags)?;
+    check_topic(deps.storage, &topic)?;
     //load current id
     let mut current_id = CURRENT_ID.load(deps.storage)?;
-    
-     //create new message
+
+    let depth = match parent_id {
+        Some(parent_id) => {
+            let mut parent = match messages().may_load(deps.storage, parent_id.u128())? {
+                Some(parent) => parent,
+                None => return Err(ContractError::InvalidMessageID {}),
+            };
+            if parent.deleted {
+                return Err(ContractError::MessageDeleted {});
+            }
+            let max_depth = CONFIG.load(deps.storage)?.max_thread_depth;
+            if parent.depth >= max_depth {
+                return Err(ContractError::MaxThreadDepthExceeded { max_depth });
+            }
+
+            parent.reply_count = parent.reply_count.checked_add(Uint128::from(1u128))?;
+            messages().save(deps.storage, parent_id.u128(), &parent)?;
+            REPLIES.save(deps.storage, (parent_id.u128(), current_id), &Empty {})?;
+            parent.depth + 1
+        }
+        None => 0,
+    };
+
+    //create new message
     let new_message = Message {
         id: Uint128::from(current_id),
-        owner: info.sender,
-        topic: topic,
-        message: message,
+        owner,
+        topic,
+        message,
+        revision: 0,
+        edited_at: None,
+        deleted: false,
+        parent_id,
+        depth,
+        reply_count: Uint128::zero(),
+        hidden: false,
+        created_at: Some(env.block.time),
+        created_height: Some(env.block.height),
+        tags,
     };
 
     //increment current id
     current_id = current_id.checked_add(1).unwrap();
 
-    MESSAGES.save(deps.storage, new_message.id.u128(), &new_message)?;
+    messages().save(deps.storage, new_message.id.u128(), &new_message)?;
+    for tag in &new_message.tags {
+        TAGGED.save(deps.storage, (tag, new_message.id.u128()), &Empty {})?;
+        TAG_COUNTS.update(deps.storage, tag, |count| -> StdResult<_> {
+            Ok(count.unwrap_or_default() + 1)
+        })?;
+    }
+    update_topic_counts(deps.storage, &new_message.topic, |t| {
+        t.message_count = t.message_count.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    let mut first_post = false;
+    update_author_stats(deps.storage, &new_message.owner, |stats| {
+        first_post = stats.messages_posted.is_zero();
+        stats.messages_posted = stats.messages_posted.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_stats(deps.storage, |stats| {
+        stats.total_messages = stats.total_messages.checked_add(Uint128::from(1u128))?;
+        stats.live_messages = stats.live_messages.checked_add(Uint128::from(1u128))?;
+        if first_post {
+            stats.authors = stats.authors.checked_add(Uint128::from(1u128))?;
+        }
+        Ok(())
+    })?;
 
     //save current id
     CURRENT_ID.save(deps.storage, &current_id)?;
 
-    Ok(Response::new()
-        .add_attribute("action", "add_message_without_funds")
-        .add_attribute("id", new_message.id.to_string()))
+    Ok(new_message)
 }
 
 pub fn like_message(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     id: Uint128,
 ) -> Result<Response, ContractError> {
     // Making sure the user has sent the right funds to like the message
-    // QUESTION: in which order the if conditions are evaluated?
-    let stipend = STIPEND.load(deps.storage)?;
-    if info.funds.len() != 1
-        || info.funds[0].denom != stipend.denom
-        || info.funds[0].amount != stipend.amount
-    {
-        return Err(ContractError::InvalidFundsMessage {
-            val1: stipend.denom,
-            val2: stipend.amount.to_string(),
-        });
-    }
+    let config = CONFIG.load(deps.storage)?;
+    let paid = match paid_coin(&info, &config.like_price) {
+        Some(paid) => paid,
+        None => {
+            return Err(ContractError::InvalidFundsLike {
+                accepted: format_coins(&config.like_price),
+            })
+        }
+    };
+    like_paid_message(deps, &env, &config, info.sender, paid, id)
+}
 
+fn like_paid_message(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    liker: Addr,
+    paid: Coin,
+    id: Uint128,
+) -> Result<Response, ContractError> {
+    check_not_banned(deps.storage, env, &liker)?;
+    check_rate_limit(deps.storage, env, &liker, RateAction::Like)?;
     // Making sure a message with the id exists. Then get the owner.
-    let owner: Addr;
-    match MESSAGES.load(deps.storage, id.u128()) {
-        Ok(message) => {
-            owner = message.owner;
-        }
+    let (owner, topic) = match messages().load(deps.storage, id.u128()) {
+        Ok(message) if message.deleted => return Err(ContractError::MessageDeleted {}),
+        Ok(message) if message.hidden => return Err(ContractError::MessageHidden {}),
+        Ok(message) => (message.owner, message.topic),
         Err(_) => return Err(ContractError::InvalidMessageID {}),
+    };
+
+    // An address can only like a message once
+    if message_likes()
+        .may_load(deps.storage, (id.u128(), &liker))?
+        .is_some()
+    {
+        return Err(ContractError::AlreadyLiked {});
     }
+    let record = MessageLike {
+        message_id: id,
+        liker: liker.clone(),
+        height: Some(env.block.height),
+        kept_fee: match config.treasury {
+            Some(_) => None,
+            None => Some(Coin::new(protocol_fee(config, &paid).u128(), &paid.denom)),
+        },
+    };
+    message_likes().save(deps.storage, (id.u128(), &liker), &record)?;
 
     // Register the like message
-    match MESSAGES_LIKE.load(deps.storage, id.u128()) {
+    let like = match MESSAGES_LIKE.load(deps.storage, id.u128()) {
         Ok(mut like) => {
             like.count = like.count.checked_add(Uint128::from(1u128)).unwrap();
-            MESSAGES_LIKE.save(deps.storage, id.u128(), &like)?;
+            like
+        }
+        Err(_) => Like {
+            id,
+            count: Uint128::from(1u128),
+        },
+    };
+    MESSAGES_LIKE.save(deps.storage, id.u128(), &like)?;
+    let previous_count = like.count - Uint128::from(1u128);
+    rank_likes(deps.storage, id.u128(), &topic, previous_count, like.count)?;
+    let trending = TRENDING.may_load(deps.storage, id.u128())?;
+    let trending = add_like(trending, env.block.height, config.trending_half_life);
+    rank_trending(deps.storage, id.u128(), &topic, Some(trending))?;
+    update_topic_counts(deps.storage, &topic, |t| {
+        t.like_count = t.like_count.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_author_stats(deps.storage, &liker, |stats| {
+        stats.likes_given = stats.likes_given.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    record_payment(deps.storage, &liker, &paid)?;
+    update_stats(deps.storage, |stats| {
+        stats.total_likes = stats.total_likes.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_author_stats(deps.storage, &owner, |stats| {
+        stats.likes_received = stats.likes_received.checked_add(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+
+    // The received funds, minus the protocol fee, are relayed to the message owner in the
+    // denom they were paid
+    let response = Response::new()
+        .add_attribute("action", "message_like")
+        .add_attribute("message_id", id);
+    Ok(pay_author(deps.storage, response, config, &owner, paid)?)
+}
+
+// Posts and likes paid with the CW20 token. The token contract calls this on behalf of the
+// user who sent the tokens.
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let price = match &config.cw20 {
+        Some(price) if price.address == info.sender => price.clone(),
+        _ => {
+            return Err(ContractError::UnacceptedCw20 {
+                address: info.sender.to_string(),
+            })
         }
-        Err(_) => {
-            let like = Like {
-                id,
-                count: Uint128::from(1u128),
+    };
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let denom = cw20_denom(&price.address);
+    let paid = Coin::new(wrapper.amount.u128(), &denom);
+
+    match from_binary(&wrapper.msg)? {
+        ReceiveMsg::AddMessage {
+            topic,
+            message,
+            parent_id,
+            tags,
+        } => {
+            if wrapper.amount != price.post_amount {
+                return Err(ContractError::InvalidFundsMessage {
+                    accepted: Coin::new(price.post_amount.u128(), denom).to_string(),
+                });
+            }
+            let post = NewPost {
+                topic,
+                message,
+                parent_id,
+                tags: tags.unwrap_or_default(),
             };
-            MESSAGES_LIKE.save(deps.storage, id.u128(), &like)?;
+            add_paid_message(deps, &env, sender, paid, post)
+        }
+        ReceiveMsg::LikeMessage { id } => {
+            if wrapper.amount != price.like_amount {
+                return Err(ContractError::InvalidFundsLike {
+                    accepted: Coin::new(price.like_amount.u128(), denom).to_string(),
+                });
+            }
+            like_paid_message(deps, &env, &config, sender, paid, id)
+        }
+    }
+}
+
+pub fn edit_message(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: Uint128,
+    message: String,
+    topic: String,
+) -> Result<Response, ContractError> {
+    let previous = match messages().may_load(deps.storage, id.u128())? {
+        Some(previous) => previous,
+        None => return Err(ContractError::InvalidMessageID {}),
+    };
+    if info.sender != previous.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if previous.deleted {
+        return Err(ContractError::MessageDeleted {});
+    }
+    let rules = CONFIG.load(deps.storage)?.content_rules;
+    let topic = normalize_topic(&topic);
+    check_topic_content(&rules, &topic)?;
+    check_message_content(&rules, &message)?;
+
+    // Moving to another topic takes the message and its likes along
+    if topic != previous.topic {
+        check_topic(deps.storage, &topic)?;
+        let likes = MESSAGES_LIKE
+            .may_load(deps.storage, id.u128())?
+            .map(|like| like.count)
+            .unwrap_or_default();
+        update_topic_counts(deps.storage, &previous.topic, |t| {
+            t.message_count = t.message_count.checked_sub(Uint128::from(1u128))?;
+            t.like_count = t.like_count.checked_sub(likes)?;
+            Ok(())
+        })?;
+        update_topic_counts(deps.storage, &topic, |t| {
+            t.message_count = t.message_count.checked_add(Uint128::from(1u128))?;
+            t.like_count = t.like_count.checked_add(likes)?;
+            Ok(())
+        })?;
+        if !likes.is_zero() {
+            TOPIC_LIKE_RANKING.remove(deps.storage, (&previous.topic, likes.u128(), id.u128()));
+            TOPIC_LIKE_RANKING.save(deps.storage, (&topic, likes.u128(), id.u128()), &Empty {})?;
+        }
+        if let Some(trending) = TRENDING.may_load(deps.storage, id.u128())? {
+            TOPIC_TRENDING_RANKING.remove(deps.storage, (&previous.topic, trending, id.u128()));
+            TOPIC_TRENDING_RANKING.save(deps.storage, (&topic, trending, id.u128()), &Empty {})?;
+        }
+    }
+
+    // Keep the version being replaced so readers can audit the edit
+    MESSAGE_HISTORY.save(deps.storage, (id.u128(), previous.revision), &previous)?;
+
+    let edited = Message {
+        topic,
+        message,
+        revision: previous.revision.checked_add(1).unwrap(),
+        edited_at: Some(env.block.time),
+        ..previous
+    };
+    messages().save(deps.storage, id.u128(), &edited)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "edit_message")
+        .add_attribute("id", id)
+        .add_attribute("revision", edited.revision.to_string()))
+}
+
+pub fn react(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: Uint128,
+    reaction: String,
+) -> Result<Response, ContractError> {
+    check_not_banned(deps.storage, &env, &info.sender)?;
+    let config = CONFIG.load(deps.storage)?;
+    let price = match config.reactions.iter().find(|r| r.name == reaction) {
+        Some(r) => r.price.clone(),
+        None => return Err(ContractError::UnknownReaction { reaction }),
+    };
+    if paid_coin(&info, std::slice::from_ref(&price)).is_none() {
+        return Err(ContractError::InvalidFundsReaction {
+            reaction,
+            accepted: format_coins(std::slice::from_ref(&price)),
+        });
+    }
+
+    let owner: Addr = match messages().load(deps.storage, id.u128()) {
+        Ok(message) if message.deleted => return Err(ContractError::MessageDeleted {}),
+        Ok(message) if message.hidden => return Err(ContractError::MessageHidden {}),
+        Ok(message) => message.owner,
+        Err(_) => return Err(ContractError::InvalidMessageID {}),
+    };
+    record_payment(deps.storage, &info.sender, &price)?;
+
+    let count = REACTION_COUNTS.update(
+        deps.storage,
+        (id.u128(), &reaction),
+        |count| -> StdResult<_> { Ok(count.unwrap_or_default() + Uint128::from(1u128)) },
+    )?;
+
+    // As with likes, the received funds are split between the message owner and the fee
+    let response = Response::new()
+        .add_attribute("action", "react")
+        .add_attribute("message_id", id)
+        .add_attribute("reaction", reaction)
+        .add_attribute("count", count);
+    Ok(pay_author(deps.storage, response, &config, &owner, price)?)
+}
+
+// Replaces the message with a tombstone: the id slot, owner and likes are kept, the text
+// and its edit history are removed.
+pub fn delete_message(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: Uint128,
+) -> Result<Response, ContractError> {
+    let message = match messages().may_load(deps.storage, id.u128())? {
+        Some(message) => message,
+        None => return Err(ContractError::InvalidMessageID {}),
+    };
+    let admin = CONFIG.load(deps.storage)?.admin;
+    if info.sender != message.owner && info.sender != admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    if message.deleted {
+        return Err(ContractError::MessageDeleted {});
+    }
+
+    let revisions = MESSAGE_HISTORY
+        .prefix(id.u128())
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<u32>>>()?;
+    for revision in revisions {
+        MESSAGE_HISTORY.remove(deps.storage, (id.u128(), revision));
+    }
+
+    let tombstone = Message {
+        message: String::new(),
+        deleted: true,
+        ..message
+    };
+    messages().save(deps.storage, id.u128(), &tombstone)?;
+    MODERATION_QUEUE.remove(deps.storage, id.u128());
+    // The tag index keeps the tombstone, only the counts drop it
+    for tag in &tombstone.tags {
+        match TAG_COUNTS.load(deps.storage, tag)? {
+            1 => TAG_COUNTS.remove(deps.storage, tag),
+            count => TAG_COUNTS.save(deps.storage, tag, &(count - 1))?,
         }
     }
+    update_stats(deps.storage, |stats| {
+        stats.live_messages = stats.live_messages.checked_sub(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "delete_message")
+        .add_attribute("id", id)
+        .add_attribute("deleted_by", info.sender))
+}
+
+// Removes the sender's like. The author share of the stipend was already relayed, so only
+// the fee share the contract kept is refunded, as far as it wasn't withdrawn.
+pub fn unlike_message(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: Uint128,
+) -> Result<Response, ContractError> {
+    let record = match message_likes().may_load(deps.storage, (id.u128(), &info.sender))? {
+        Some(record) => record,
+        None => return Err(ContractError::NotLiked {}),
+    };
+    message_likes().remove(deps.storage, (id.u128(), &info.sender))?;
+
+    let mut like = MESSAGES_LIKE.load(deps.storage, id.u128())?;
+    like.count = like.count.checked_sub(Uint128::from(1u128))?;
+    MESSAGES_LIKE.save(deps.storage, id.u128(), &like)?;
+    let Message { owner, topic, .. } = messages().load(deps.storage, id.u128())?;
+    let previous_count = like.count + Uint128::from(1u128);
+    rank_likes(deps.storage, id.u128(), &topic, previous_count, like.count)?;
+    // Likes from before the trending score was kept never made it into the score
+    if let (Some(height), Some(trending)) =
+        (record.height, TRENDING.may_load(deps.storage, id.u128())?)
+    {
+        let half_life = CONFIG.load(deps.storage)?.trending_half_life;
+        let trending = match like.count.is_zero() {
+            true => None,
+            false => remove_like(trending, height, half_life),
+        };
+        rank_trending(deps.storage, id.u128(), &topic, trending)?;
+    }
+    update_topic_counts(deps.storage, &topic, |t| {
+        t.like_count = t.like_count.checked_sub(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_author_stats(deps.storage, &info.sender, |stats| {
+        stats.likes_given = stats.likes_given.checked_sub(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_author_stats(deps.storage, &owner, |stats| {
+        stats.likes_received = stats.likes_received.checked_sub(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+    update_stats(deps.storage, |stats| {
+        stats.total_likes = stats.total_likes.checked_sub(Uint128::from(1u128))?;
+        Ok(())
+    })?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "message_unlike")
+        .add_attribute("message_id", id)
+        .add_attribute("count", like.count);
+    if let Some(fee) = record.kept_fee {
+        let refund = refund_fee(deps.storage, &fee)?;
+        response = response.add_attribute("refund", refund.to_string());
+        if !refund.amount.is_zero() {
+            response = response.add_message(send_coin(&info.sender, refund)?);
+        }
+    }
+    Ok(response)
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(admin) = msg.admin {
+        config.admin = deps.api.addr_validate(&admin)?;
+    }
+    if let Some(post_price) = msg.post_price {
+        validate_stipend(&post_price)?;
+        config.post_price = post_price;
+    }
+    if let Some(like_price) = msg.like_price {
+        validate_stipend(&like_price)?;
+        config.like_price = like_price;
+    }
+    if let Some(fee_bps) = msg.fee_bps {
+        validate_fee_bps(fee_bps)?;
+        config.fee_bps = fee_bps;
+    }
+    if let Some(treasury) = msg.treasury {
+        config.treasury = match treasury.as_str() {
+            "" => None,
+            treasury => Some(deps.api.addr_validate(treasury)?),
+        };
+    }
+    if let Some(max_thread_depth) = msg.max_thread_depth {
+        config.max_thread_depth = max_thread_depth;
+    }
+    if let Some(reactions) = msg.reactions {
+        validate_reactions(&reactions)?;
+        config.reactions = reactions;
+    }
+    if let Some(strict_topics) = msg.strict_topics {
+        config.strict_topics = strict_topics;
+    }
+    if let Some(topic_creation) = msg.topic_creation {
+        config.topic_creation = topic_creation;
+    }
+    if let Some(topic_price) = msg.topic_price {
+        validate_topic_price(&topic_price)?;
+        config.topic_price = topic_price;
+    }
+    if let Some(auto_hide_reports) = msg.auto_hide_reports {
+        config.auto_hide_reports = auto_hide_reports;
+    }
+    if let Some(rate_limit) = msg.rate_limit {
+        config.rate_limit = (rate_limit.window_blocks > 0).then_some(rate_limit);
+    }
+    if let Some(trending_half_life) = msg.trending_half_life {
+        validate_half_life(trending_half_life)?;
+        // Scores kept under the old half-life would read wrong under the new one
+        if trending_half_life != config.trending_half_life
+            && TRENDING.keys(deps.storage, None, None, Order::Ascending).next().is_some()
+        {
+            return Err(ContractError::HalfLifeLocked {});
+        }
+        config.trending_half_life = trending_half_life;
+    }
+    if let Some(content_rules) = msg.content_rules {
+        validate_content_rules(&content_rules)?;
+        config.content_rules = content_rules;
+    }
+    if let Some(cw20) = msg.cw20 {
+        config.cw20 = match cw20.address.as_str() {
+            "" => None,
+            _ => Some(validate_cw20_price(deps.api, cw20)?),
+        };
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("admin", config.admin)
+        .add_attribute("post_price", format_coins(&config.post_price))
+        .add_attribute("like_price", format_coins(&config.like_price))
+        .add_attribute("fee_bps", config.fee_bps.to_string())
+        .add_attribute("max_thread_depth", config.max_thread_depth.to_string()))
+}
+
+pub fn report_message(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: Uint128,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut message = match messages().may_load(deps.storage, id.u128())? {
+        Some(message) if message.deleted => return Err(ContractError::MessageDeleted {}),
+        Some(message) if message.hidden => return Err(ContractError::MessageHidden {}),
+        Some(message) => message,
+        None => return Err(ContractError::InvalidMessageID {}),
+    };
+    if REPORTS.has(deps.storage, (id.u128(), &info.sender)) {
+        return Err(ContractError::AlreadyReported {});
+    }
+    let max_length = CONFIG.load(deps.storage)?.content_rules.max_message_length;
+    if reason.chars().count() > max_length as usize {
+        return Err(ContractError::ReasonTooLong { max_length });
+    }
+    REPORTS.save(deps.storage, (id.u128(), &info.sender), &reason)?;
+    let report_count = MODERATION_QUEUE
+        .may_load(deps.storage, id.u128())?
+        .unwrap_or_default()
+        + 1;
+    MODERATION_QUEUE.save(deps.storage, id.u128(), &report_count)?;
+
+    // Enough reports hide the message until a moderator looks at it
+    let auto_hide_reports = CONFIG.load(deps.storage)?.auto_hide_reports;
+    if auto_hide_reports > 0 && report_count >= auto_hide_reports {
+        message.hidden = true;
+        messages().save(deps.storage, id.u128(), &message)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "report_message")
+        .add_attribute("id", id)
+        .add_attribute("report_count", report_count.to_string())
+        .add_attribute("hidden", message.hidden.to_string()))
+}
+
+pub fn update_moderators(
+    deps: DepsMut,
+    info: MessageInfo,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Response, ContractError> {
+    if info.sender != CONFIG.load(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    for address in &add {
+        let address = deps.api.addr_validate(address)?;
+        MODERATORS.save(deps.storage, &address, &Empty {})?;
+    }
+    for address in &remove {
+        let address = deps.api.addr_validate(address)?;
+        MODERATORS.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "update_moderators")
+        .add_attribute("added", add.join(","))
+        .add_attribute("removed", remove.join(",")))
+}
+
+// Hides or restores a message. Either way the moderator has dealt with its reports, so
+// it leaves the queue. Restoring dismisses the reports, so they count again from zero.
+pub fn moderate_message(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: Uint128,
+    hidden: bool,
+) -> Result<Response, ContractError> {
+    check_moderator(deps.storage, &info.sender)?;
+    let mut message = match messages().may_load(deps.storage, id.u128())? {
+        Some(message) if message.deleted => return Err(ContractError::MessageDeleted {}),
+        Some(message) => message,
+        None => return Err(ContractError::InvalidMessageID {}),
+    };
+    message.hidden = hidden;
+    messages().save(deps.storage, id.u128(), &message)?;
+    MODERATION_QUEUE.remove(deps.storage, id.u128());
+    if !hidden {
+        let reporters = REPORTS
+            .prefix(id.u128())
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<Addr>>>()?;
+        for reporter in reporters {
+            REPORTS.remove(deps.storage, (id.u128(), &reporter));
+        }
+    }
+
+    let action = if hidden { "hide_message" } else { "restore_message" };
+    Ok(Response::new()
+        .add_attribute("action", action)
+        .add_attribute("id", id)
+        .add_attribute("moderator", info.sender))
+}
+
+pub fn ban(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    until_height: Option<u64>,
+) -> Result<Response, ContractError> {
+    check_moderator(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    let ban = Ban {
+        address,
+        until_height,
+        banned_by: info.sender,
+    };
+    BANS.save(deps.storage, &ban.address, &ban)?;
+
+    let until_height = until_height.map_or_else(|| "forever".to_string(), |h| h.to_string());
+    Ok(Response::new()
+        .add_attribute("action", "ban")
+        .add_attribute("address", ban.address)
+        .add_attribute("until_height", until_height))
+}
+
+pub fn unban(deps: DepsMut, info: MessageInfo, address: String) -> Result<Response, ContractError> {
+    check_moderator(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    BANS.remove(deps.storage, &address);
+
+    Ok(Response::new()
+        .add_attribute("action", "unban")
+        .add_attribute("address", address))
+}
+
+pub fn create_topic(
+    deps: DepsMut,
+    info: MessageInfo,
+    name: String,
+    title: String,
+    description: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.topic_creation == TopicCreation::AdminOnly && info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let name = normalize_topic(&name);
+    check_topic_content(&config.content_rules, &name)?;
+    check_topic_details(&config.content_rules, &title, &description)?;
+    if TOPICS.has(deps.storage, &name) {
+        return Err(ContractError::TopicExists { topic: name });
+    }
+    if !config.topic_price.is_empty() {
+        let paid = match paid_coin(&info, &config.topic_price) {
+            Some(paid) => paid,
+            None => {
+                return Err(ContractError::InvalidFundsTopic {
+                    accepted: format_coins(&config.topic_price),
+                })
+            }
+        };
+        record_fee(deps.storage, &paid)?;
+        record_payment(deps.storage, &info.sender, &paid)?;
+    }
+
+    // Messages may already use the topic, the counters start from them
+    let counts = TOPIC_COUNTS.may_load(deps.storage, &name)?.unwrap_or_default();
+    let topic = Topic {
+        name,
+        title,
+        description,
+        creator: info.sender,
+        message_count: counts.message_count,
+        like_count: counts.like_count,
+    };
+    TOPICS.save(deps.storage, &topic.name, &topic)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_topic")
+        .add_attribute("topic", topic.name))
+}
+
+pub fn grant_sponsored_posts(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    quota: u32,
+) -> Result<Response, ContractError> {
+    if info.sender != CONFIG.load(deps.storage)?.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    if quota == 0 {
+        SPONSORED_POSTS.remove(deps.storage, &address);
+    } else {
+        SPONSORED_POSTS.save(deps.storage, &address, &quota)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "grant_sponsored_posts")
+        .add_attribute("address", address)
+        .add_attribute("quota", quota.to_string()))
+}
+
+// At least one coin, one per denom. CW20 fees are withdrawn with their `cw20:<token address>`
+// denom, which has to hold a valid address.
+fn validate_withdrawal(api: &dyn Api, amount: &[Coin]) -> Result<(), ContractError> {
+    if amount.is_empty() {
+        return Err(ContractError::InvalidWithdrawal {});
+    }
+    for (i, coin) in amount.iter().enumerate() {
+        if coin.denom.is_empty()
+            || coin.amount.is_zero()
+            || amount[..i].iter().any(|c| c.denom == coin.denom)
+        {
+            return Err(ContractError::InvalidWithdrawal {});
+        }
+        if let Some(token) = coin.denom.strip_prefix(CW20_DENOM_PREFIX) {
+            api.addr_validate(token).map_err(|_| ContractError::InvalidWithdrawal {})?;
+        }
+    }
+    Ok(())
+}
+
+// Sends fees held by the contract. The amount can't exceed what is left of each denom.
+pub fn withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+    amount: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let to = deps.api.addr_validate(&to)?;
+    validate_withdrawal(deps.api, &amount)?;
+
+    for coin in &amount {
+        let available = FEES
+            .may_load(deps.storage, &coin.denom)?
+            .unwrap_or_default();
+        if coin.amount > available {
+            return Err(ContractError::InsufficientFees {
+                denom: coin.denom.clone(),
+                available,
+            });
+        }
+        FEES.save(deps.storage, &coin.denom, &(available - coin.amount))?;
+    }
+
+    let mut response = Response::new()
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("to", to.to_string())
+        .add_attribute("amount", format_coins(&amount));
+    for coin in amount {
+        response = response.add_message(send_coin(&to, coin)?);
+    }
+    Ok(response)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // v0.1.0 was deployed before cw2 versioning was wired in, so it has no version info.
+    let previous_version = match cw2::CONTRACT.may_load(deps.storage)? {
+        Some(info) => {
+            if info.contract != CONTRACT_NAME {
+                return Err(ContractError::CannotMigrate {
+                    previous_contract: info.contract,
+                });
+            }
+            info.version.parse::<Version>()?
+        }
+        None => Version::new(0, 1, 0),
+    };
+    let new_version = CONTRACT_VERSION.parse::<Version>()?;
+    if previous_version > new_version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: previous_version.to_string(),
+            new_version: new_version.to_string(),
+        });
+    }
+
+    let admin = msg
+        .admin
+        .map(|admin| deps.api.addr_validate(&admin))
+        .transpose()?;
+    if previous_version < Version::new(0, 2, 0) {
+        migrations::v0_1::migrate(deps.storage, admin)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", previous_version.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetCurrentId {} => to_binary(&query_current_id(deps)?),
+        QueryMsg::GetAllMessage {
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_all_messages(
+            deps,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetMessagesByAddr {
+            address,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_messages_by_addr(
+            deps,
+            address,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetMessagesByTopic {
+            topic,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_messages_by_topic(
+            deps,
+            topic,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetMessagesBetween {
+            start,
+            end,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_messages_between(
+            deps,
+            start,
+            end,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetMessagesByTag {
+            tag,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_messages_by_tags(
+            deps,
+            vec![tag],
+            TagMatch::All,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetMessagesByTags {
+            tags,
+            mode,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_messages_by_tags(
+            deps,
+            tags,
+            mode,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::ListTags {
+            prefix,
+            start_after,
+            limit,
+        } => to_binary(&query_tags(deps, prefix, start_after, limit)?),
+        QueryMsg::GetTopMessages { topic, limit } => {
+            to_binary(&query_top_messages(deps, topic, limit)?)
+        }
+        QueryMsg::GetTrending { topic, limit } => {
+            to_binary(&query_trending(deps, env, topic, limit)?)
+        }
+        QueryMsg::GetMessagesById { id } => to_binary(&query_messages_by_id(deps, id)?),
+        QueryMsg::GetLikesById { id } => to_binary(&query_likes_by_id(deps, id)?),
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetModerationQueue { start_after, limit } => {
+            to_binary(&query_moderation_queue(deps, start_after, limit)?)
+        }
+        QueryMsg::GetReports {
+            id,
+            start_after,
+            limit,
+        } => to_binary(&query_reports(deps, id, start_after, limit)?),
+        QueryMsg::GetModerators { start_after, limit } => {
+            to_binary(&query_moderators(deps, start_after, limit)?)
+        }
+        QueryMsg::ListBans { start_after, limit } => {
+            to_binary(&query_bans(deps, start_after, limit)?)
+        }
+        QueryMsg::GetTopic { name } => to_binary(&query_topic(deps, name)?),
+        QueryMsg::ListTopics { start_after, limit } => {
+            to_binary(&query_topics(deps, start_after, limit)?)
+        }
+        QueryMsg::GetRateLimitQuota { address } => {
+            to_binary(&query_rate_limit_quota(deps, env, address)?)
+        }
+        QueryMsg::GetSponsoredQuota { address } => {
+            to_binary(&query_sponsored_quota(deps, address)?)
+        }
+        QueryMsg::GetTreasury {} => to_binary(&query_treasury(deps)?),
+        QueryMsg::GetStats {} => to_binary(&query_stats(deps)?),
+        QueryMsg::GetAuthorStats { address } => {
+            to_binary(&query_author_stats(deps, address)?)
+        }
+        QueryMsg::ListTopAuthors { start_after, limit } => {
+            to_binary(&query_top_authors(deps, start_after, limit)?)
+        }
+        QueryMsg::GetReactions { id } => to_binary(&query_reactions(deps, id)?),
+        QueryMsg::GetReplies {
+            id,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_replies(
+            deps,
+            id,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+        QueryMsg::GetThread {
+            root_id,
+            start_after,
+            limit,
+        } => to_binary(&query_thread(deps, root_id, start_after, limit)?),
+        QueryMsg::GetMessageHistory {
+            id,
+            start_after,
+            limit,
+        } => to_binary(&query_message_history(deps, id, start_after, limit)?),
+        QueryMsg::GetLikers {
+            id,
+            start_after,
+            limit,
+        } => to_binary(&query_likers(deps, id, start_after, limit)?),
+        QueryMsg::GetLikedMessages {
+            address,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        } => to_binary(&query_liked_messages(
+            deps,
+            address,
+            start_after,
+            limit,
+            order,
+            include_deleted,
+        )?),
+    }
+}
+
+fn query_current_id(deps: Deps) -> StdResult<Uint128> {
+    let current_id = CURRENT_ID.load(deps.storage)?;
+    Ok(Uint128::from(current_id))
+}
+
+// Turns the `start_after` cursor into range bounds. The cursor is excluded from the page
+// and bounds the side of the range we are walking away from.
+fn page_bounds<'a, K: PrimaryKey<'a>>(
+    start_after: Option<K>,
+    order: Option<OrderBy>,
+) -> (Option<Bound<'a, K>>, Option<Bound<'a, K>>, Order) {
+    let order: Order = order.unwrap_or(OrderBy::Asc).into();
+    let bound = start_after.map(Bound::exclusive);
+    match order {
+        Order::Ascending => (bound, None, order),
+        Order::Descending => (None, bound, order),
+    }
+}
+
+// Collects up to `limit` items, with the cursor of the last one when more items follow.
+fn page<T, C>(
+    iter: impl Iterator<Item = StdResult<T>>,
+    limit: usize,
+    cursor: impl FnOnce(&T) -> C,
+) -> StdResult<(Vec<T>, Option<C>)> {
+    let mut items = iter.take(limit + 1).collect::<StdResult<Vec<_>>>()?;
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(cursor)
+    } else {
+        None
+    };
+    Ok((items, next_cursor))
+}
+
+// Hidden messages keep their place wherever they are listed, but their text is only shown
+// in the moderation queue until a moderator restores them.
+fn without_hidden_text(message: Message) -> Message {
+    if message.hidden {
+        Message {
+            message: String::new(),
+            ..message
+        }
+    } else {
+        message
+    }
+}
+
+// Reads up to `limit` messages (one extra to know whether there is a next page).
+// Tombstones and hidden messages are skipped unless `include_deleted` is set.
+fn messages_page(
+    iter: impl Iterator<Item = StdResult<(u128, Message)>>,
+    limit: Option<u32>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let include_deleted = include_deleted.unwrap_or(false);
+    let (messages, next_cursor) = page(
+        iter
+            .map(|item| item.map(|(_, message)| message))
+            .filter(|item| {
+                include_deleted || !matches!(item, Ok(message) if message.deleted || message.hidden)
+            })
+            .map(|item| item.map(without_hidden_text)),
+        limit,
+        |message| message.id,
+    )?;
+    Ok(MessagesResponse {
+        messages,
+        next_cursor,
+    })
+}
+
+fn query_all_messages(
+    deps: Deps,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), order);
+    messages_page(
+        messages().range(deps.storage, min, max, order),
+        limit,
+        include_deleted,
+    )
+}
+
+fn query_messages_by_addr(
+    deps: Deps,
+    address: String,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let owner = deps.api.addr_validate(&address)?;
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), order);
+    messages_page(
+        messages()
+            .idx
+            .owner
+            .prefix(owner)
+            .range(deps.storage, min, max, order),
+        limit,
+        include_deleted,
+    )
+}
+
+fn query_messages_by_topic(
+    deps: Deps,
+    topic: String,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let topic = normalize_topic(&topic);
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), order);
+    messages_page(
+        messages()
+            .idx
+            .topic
+            .prefix(topic)
+            .range(deps.storage, min, max, order),
+        limit,
+        include_deleted,
+    )
+}
+
+fn query_messages_between(
+    deps: Deps,
+    start: Timestamp,
+    end: Timestamp,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let order: Order = order.unwrap_or(OrderBy::Asc).into();
+    let mut min = Some(Bound::inclusive((start.nanos(), 0)));
+    let mut max = Some(Bound::exclusive((end.nanos(), 0)));
+    // Messages can share a time, so the cursor is the (time, id) of the last one returned.
+    // It only narrows the range, a cursor outside of it doesn't widen it.
+    if let Some(id) = start_after {
+        let cursor = messages().load(deps.storage, id.u128())?;
+        let cursor = (created_nanos(&cursor), id.u128());
+        match order {
+            Order::Ascending if cursor >= (start.nanos(), 0) => min = Some(Bound::exclusive(cursor)),
+            Order::Descending if cursor <= (end.nanos(), 0) => max = Some(Bound::exclusive(cursor)),
+            _ => {}
+        }
+    }
+    messages_page(
+        messages()
+            .idx
+            .created
+            .range(deps.storage, min, max, order),
+        limit,
+        include_deleted,
+    )
+}
+
+// With All, the ids of the least used tag are checked against the others. With Any, the
+// ids of every tag are merged in order.
+fn query_messages_by_tags(
+    deps: Deps,
+    tags: Vec<String>,
+    mode: TagMatch,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let mut tags: Vec<String> = tags.iter().map(|tag| normalize_topic(tag)).collect();
+    tags.sort();
+    tags.dedup();
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), order);
+    let tagged = |tag: &str| {
+        TAGGED
+            .prefix(tag)
+            .keys(deps.storage, min.clone(), max.clone(), order)
+    };
+
+    let ids: Box<dyn Iterator<Item = StdResult<u128>>> = match mode {
+        TagMatch::All => {
+            let counts = tags
+                .iter()
+                .map(|tag| Ok(TAG_COUNTS.may_load(deps.storage, tag)?.unwrap_or_default()))
+                .collect::<StdResult<Vec<_>>>()?;
+            let rarest = match counts.iter().enumerate().min_by_key(|(_, count)| **count) {
+                Some((rarest, _)) => tags.remove(rarest),
+                None => return messages_page(std::iter::empty(), limit, include_deleted),
+            };
+            Box::new(tagged(&rarest).filter(move |id| match id {
+                Ok(id) => tags.iter().all(|tag| TAGGED.has(deps.storage, (tag, *id))),
+                Err(_) => true,
+            }))
+        }
+        TagMatch::Any => {
+            let mut sources: Vec<_> = tags.iter().map(|tag| tagged(tag).peekable()).collect();
+            Box::new(std::iter::from_fn(move || {
+                let mut next: Option<u128> = None;
+                for source in sources.iter_mut() {
+                    match source.peek() {
+                        Some(Ok(id)) => {
+                            let first = match (next, order) {
+                                (None, _) => true,
+                                (Some(next), Order::Ascending) => *id < next,
+                                (Some(next), Order::Descending) => *id > next,
+                            };
+                            if first {
+                                next = Some(*id);
+                            }
+                        }
+                        Some(Err(_)) => return source.next(),
+                        None => {}
+                    }
+                }
+                let next = next?;
+                for source in sources.iter_mut() {
+                    if matches!(source.peek(), Some(Ok(id)) if *id == next) {
+                        source.next();
+                    }
+                }
+                Some(Ok(next))
+            }))
+        }
+    };
+    let tagged_messages = ids.map(|id| {
+        let id = id?;
+        Ok((id, messages().load(deps.storage, id)?))
+    });
+    messages_page(tagged_messages, limit, include_deleted)
+}
+
+fn query_tags(
+    deps: Deps,
+    prefix: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TagsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let prefix = prefix.as_deref().map(normalize_topic).unwrap_or_default();
+    // Tags starting with the prefix sort right from the prefix itself
+    let min = match start_after.as_deref() {
+        Some(start_after) if start_after >= prefix.as_str() => Bound::exclusive(start_after),
+        _ => Bound::inclusive(prefix.as_str()),
+    };
+
+    let (tags, next_cursor) = page(
+        TAG_COUNTS
+            .range(deps.storage, Some(min), None, Order::Ascending)
+            .take_while(|item| !matches!(item, Ok((tag, _)) if !tag.starts_with(&prefix)))
+            .map(|item| item.map(|(tag, count)| TagCount { tag, count })),
+        limit,
+        |tag| tag.tag.clone(),
+    )?;
+    Ok(TagsResponse { tags, next_cursor })
+}
+
+// Most liked messages first. Deleted and hidden messages are left out.
+fn query_top_messages(
+    deps: Deps,
+    topic: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TopMessagesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let topic = topic.as_deref().map(normalize_topic);
+    let ranking: Box<dyn Iterator<Item = StdResult<(u128, u128)>>> = match &topic {
+        Some(topic) => TOPIC_LIKE_RANKING.sub_prefix(topic).keys(
+            deps.storage,
+            None,
+            None,
+            Order::Descending,
+        ),
+        None => LIKE_RANKING.keys(deps.storage, None, None, Order::Descending),
+    };
+    let messages = ranking
+        .map(|key| {
+            let (likes, id) = key?;
+            Ok(RankedMessage {
+                message: messages().load(deps.storage, id)?,
+                likes: Uint128::new(likes),
+            })
+        })
+        .filter(|item| {
+            !matches!(item, Ok(ranked) if ranked.message.deleted || ranked.message.hidden)
+        })
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(TopMessagesResponse { messages })
+}
+
+// Highest trending score first. Deleted and hidden messages are left out.
+fn query_trending(
+    deps: Deps,
+    env: Env,
+    topic: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TrendingResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let half_life = CONFIG.load(deps.storage)?.trending_half_life;
+    let topic = topic.as_deref().map(normalize_topic);
+    let ranking: Box<dyn Iterator<Item = StdResult<(u128, u128)>>> = match &topic {
+        Some(topic) => TOPIC_TRENDING_RANKING.sub_prefix(topic).keys(
+            deps.storage,
+            None,
+            None,
+            Order::Descending,
+        ),
+        None => TRENDING_RANKING.keys(deps.storage, None, None, Order::Descending),
+    };
+    let messages = ranking
+        .map(|key| {
+            let (score, id) = key?;
+            Ok(TrendingMessage {
+                message: messages().load(deps.storage, id)?,
+                score: current_score(score, env.block.height, half_life),
+            })
+        })
+        .filter(|item| {
+            !matches!(item, Ok(trending) if trending.message.deleted || trending.message.hidden)
+        })
+        .take(limit)
+        .collect::<StdResult<_>>()?;
+    Ok(TrendingResponse { messages })
+}
+
+fn query_messages_by_id(deps: Deps, id: Uint128) -> StdResult<MessagesResponse> {
+    let message = messages().load(deps.storage, id.u128())?;
+    Ok(MessagesResponse {
+        messages: vec![without_hidden_text(message)],
+        next_cursor: None,
+    })
+}
+
+fn query_reactions(deps: Deps, id: Uint128) -> StdResult<ReactionsResponse> {
+    let reactions = REACTION_COUNTS
+        .prefix(id.u128())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(reaction, count)| ReactionCount { reaction, count }))
+        .collect::<StdResult<_>>()?;
+    Ok(ReactionsResponse { id, reactions })
+}
+
+fn query_replies(
+    deps: Deps,
+    id: Uint128,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), order);
+    let replies = REPLIES
+        .prefix(id.u128())
+        .keys(deps.storage, min, max, order)
+        .map(|reply_id| {
+            let reply_id = reply_id?;
+            Ok((reply_id, messages().load(deps.storage, reply_id)?))
+        });
+    messages_page(replies, limit, include_deleted)
+}
+
+// First reply to the message after `start_after`, in id order
+fn next_reply(storage: &dyn Storage, id: u128, start_after: Option<u128>) -> StdResult<Option<u128>> {
+    REPLIES
+        .prefix(id)
+        .keys(storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+        .next()
+        .transpose()
+}
+
+// Message following `message` in a depth first walk of the thread: its first reply, or else
+// the next reply to the closest ancestor that has one. None once the walk is back at the root.
+fn next_in_thread(
+    storage: &dyn Storage,
+    root_id: u128,
+    max_depth: u32,
+    message: &Message,
+) -> StdResult<Option<Message>> {
+    if message.depth < max_depth {
+        if let Some(id) = next_reply(storage, message.id.u128(), None)? {
+            return messages().load(storage, id).map(Some);
+        }
+    }
+    let mut current = message.clone();
+    while current.id.u128() != root_id {
+        // Only a message outside of the thread has no parent before the root
+        let parent_id = match current.parent_id {
+            Some(parent_id) => parent_id.u128(),
+            None => return Ok(None),
+        };
+        if let Some(id) = next_reply(storage, parent_id, Some(current.id.u128()))? {
+            return messages().load(storage, id).map(Some);
+        }
+        current = messages().load(storage, parent_id)?;
+    }
+    Ok(None)
+}
+
+fn query_thread(
+    deps: Deps,
+    root_id: Uint128,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+) -> StdResult<MessagesResponse> {
+    let max_depth = CONFIG.load(deps.storage)?.max_thread_depth;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut next = match start_after {
+        Some(id) => {
+            let message = messages().load(deps.storage, id.u128())?;
+            // The cursor has to be a reply somewhere under the root
+            let mut ancestor = message.clone();
+            while ancestor.id != root_id {
+                ancestor = match ancestor.parent_id {
+                    Some(parent_id) => messages().load(deps.storage, parent_id.u128())?,
+                    None => {
+                        return Err(StdError::generic_err(format!(
+                            "Message {} is not in the thread of message {}",
+                            id, root_id
+                        )))
+                    }
+                };
+            }
+            next_in_thread(deps.storage, root_id.u128(), max_depth, &message)?
+        }
+        None => Some(messages().load(deps.storage, root_id.u128())?),
+    };
+
+    let mut thread = vec![];
+    while let Some(message) = next {
+        if thread.len() == limit {
+            next = Some(message);
+            break;
+        }
+        next = next_in_thread(deps.storage, root_id.u128(), max_depth, &message)?;
+        thread.push(without_hidden_text(message));
+    }
+
+    // More messages follow when the walk stopped at the limit
+    let next_cursor = match next {
+        Some(_) => thread.last().map(|message| message.id),
+        None => None,
+    };
+    Ok(MessagesResponse {
+        messages: thread,
+        next_cursor,
+    })
+}
+
+fn query_message_history(
+    deps: Deps,
+    id: Uint128,
+    start_after: Option<u32>,
+    limit: Option<u32>,
+) -> StdResult<MessageHistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.map(Bound::exclusive);
+    // Revisions were saved before the message was hidden, so they follow the message itself
+    let hidden = messages().load(deps.storage, id.u128())?.hidden;
+
+    let (revisions, next_cursor) = page(
+        MESSAGE_HISTORY
+            .prefix(id.u128())
+            .range(deps.storage, min, None, Order::Ascending)
+            .map(|item| {
+                item.map(|(_, message)| without_hidden_text(Message { hidden, ..message }))
+            }),
+        limit,
+        |message| message.revision,
+    )?;
+    Ok(MessageHistoryResponse {
+        revisions,
+        next_cursor,
+    })
+}
+
+fn query_likers(
+    deps: Deps,
+    id: Uint128,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<LikersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let min = start_after.as_ref().map(Bound::exclusive);
+
+    let (likers, next_cursor) = page(
+        message_likes()
+            .prefix(id.u128())
+            .keys(deps.storage, min, None, Order::Ascending),
+        limit,
+        Addr::clone,
+    )?;
+    Ok(LikersResponse {
+        likers,
+        next_cursor,
+    })
+}
+
+fn query_liked_messages(
+    deps: Deps,
+    address: String,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+    order: Option<OrderBy>,
+    include_deleted: Option<bool>,
+) -> StdResult<MessagesResponse> {
+    let liker = deps.api.addr_validate(&address)?;
+    // The liker index is keyed by (message id, liker), so the cursor needs the liker too
+    let start_after = start_after.map(|id| (id.u128(), liker.clone()));
+    let (min, max, order) = page_bounds(start_after, order);
+
+    let liked = message_likes()
+        .idx
+        .liker
+        .prefix(liker)
+        .keys(deps.storage, min, max, order)
+        .map(|key| {
+            let (id, _) = key?;
+            Ok((id, messages().load(deps.storage, id)?))
+        });
+    messages_page(liked, limit, include_deleted)
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        admin: config.admin,
+        post_price: config.post_price,
+        like_price: config.like_price,
+        fee_bps: config.fee_bps,
+        treasury: config.treasury,
+        max_thread_depth: config.max_thread_depth,
+        reactions: config.reactions,
+        cw20: config.cw20,
+        strict_topics: config.strict_topics,
+        topic_creation: config.topic_creation,
+        topic_price: config.topic_price,
+        auto_hide_reports: config.auto_hide_reports,
+        rate_limit: config.rate_limit,
+        trending_half_life: config.trending_half_life,
+        content_rules: config.content_rules,
+    })
+}
+
+fn query_moderation_queue(
+    deps: Deps,
+    start_after: Option<Uint128>,
+    limit: Option<u32>,
+) -> StdResult<ModerationQueueResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let (min, max, order) = page_bounds(start_after.map(|id| id.u128()), None);
+
+    let (reported, next_cursor) = page(
+        MODERATION_QUEUE
+            .range(deps.storage, min, max, order)
+            .map(|item| {
+                let (id, report_count) = item?;
+                let message = messages().load(deps.storage, id)?;
+                Ok(ReportedMessage {
+                    message,
+                    report_count,
+                })
+            }),
+        limit,
+        |reported| reported.message.id,
+    )?;
+    Ok(ModerationQueueResponse {
+        messages: reported,
+        next_cursor,
+    })
+}
+
+fn query_reports(
+    deps: Deps,
+    id: Uint128,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ReportsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let min = start_after.as_ref().map(Bound::exclusive);
+
+    let (reports, next_cursor) = page(
+        REPORTS
+            .prefix(id.u128())
+            .range(deps.storage, min, None, Order::Ascending)
+            .map(|item| item.map(|(reporter, reason)| Report { reporter, reason })),
+        limit,
+        |report| report.reporter.clone(),
+    )?;
+    Ok(ReportsResponse {
+        reports,
+        next_cursor,
+    })
+}
+
+fn query_moderators(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ModeratorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let min = start_after.as_ref().map(Bound::exclusive);
+
+    let (moderators, next_cursor) = page(
+        MODERATORS.keys(deps.storage, min, None, Order::Ascending),
+        limit,
+        Addr::clone,
+    )?;
+    Ok(ModeratorsResponse {
+        moderators,
+        next_cursor,
+    })
+}
 
-    // The received funds will be relayed to the message owner
-    let v_stipend = vec![stipend];
-    let msg = BankMsg::Send {
-        to_address: owner.to_string(),
-        amount: v_stipend,
-    };
+fn query_bans(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BansResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let min = start_after.as_ref().map(Bound::exclusive);
 
-    Ok(Response::new()
-        .add_attribute("action", "message_like")
-        .add_attribute("message_id", id)
-        .add_attribute("sent_to", owner.to_string())
-        .add_message(msg))
+    let (bans, next_cursor) = page(
+        BANS
+            .range(deps.storage, min, None, Order::Ascending)
+            .map(|item| item.map(|(_, ban)| ban)),
+        limit,
+        |ban| ban.address.clone(),
+    )?;
+    Ok(BansResponse { bans, next_cursor })
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetCurrentId {} => to_binary(&query_current_id(deps)?),
-        QueryMsg::GetAllMessage {} => to_binary(&query_all_messages(deps)?),
-        QueryMsg::GetMessagesByAddr { address } => {
-            to_binary(&query_messages_by_addr(deps, address)?)
+fn query_topic(deps: Deps, name: String) -> StdResult<TopicResponse> {
+    let topic = TOPICS.load(deps.storage, &normalize_topic(&name))?;
+    Ok(TopicResponse { topic })
+}
+
+fn query_topics(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TopicsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = start_after.as_deref().map(Bound::exclusive);
+
+    let (topics, next_cursor) = page(
+        TOPICS
+            .range(deps.storage, min, None, Order::Ascending)
+            .map(|item| item.map(|(_, topic)| topic)),
+        limit,
+        |topic| topic.name.clone(),
+    )?;
+    Ok(TopicsResponse {
+        topics,
+        next_cursor,
+    })
+}
+
+fn query_rate_limit_quota(
+    deps: Deps,
+    env: Env,
+    address: String,
+) -> StdResult<RateLimitQuotaResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let rate_limit = match CONFIG.load(deps.storage)?.rate_limit {
+        Some(rate_limit) => rate_limit,
+        None => {
+            return Ok(RateLimitQuotaResponse {
+                posts_left: None,
+                likes_left: None,
+                reset_height: None,
+            })
         }
-        QueryMsg::GetMessagesByTopic { topic } => to_binary(&query_messages_by_topic(deps, topic)?),
-        QueryMsg::GetMessagesById { id } => to_binary(&query_messages_by_id(deps, id)?),
-        QueryMsg::GetLikesById { id } => to_binary(&query_likes_by_id(deps, id)?),
-    }
+    };
+    let window = rate_window(deps.storage, &env, &address, &rate_limit)?;
+    let left = |limit: u32, used: u32| (limit > 0).then(|| limit.saturating_sub(used));
+    let used_any = window.posts > 0 || window.likes > 0;
+    Ok(RateLimitQuotaResponse {
+        posts_left: left(rate_limit.max_posts, window.posts),
+        likes_left: left(rate_limit.max_likes, window.likes),
+        reset_height: used_any.then(|| window_end(&window, &rate_limit)),
+    })
 }
 
-fn query_current_id(deps: Deps) -> StdResult<Uint128> {
-    let current_id = CURRENT_ID.load(deps.storage)?;
-    Ok(Uint128::from(current_id))
+fn query_sponsored_quota(deps: Deps, address: String) -> StdResult<SponsoredQuotaResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let remaining = SPONSORED_POSTS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(SponsoredQuotaResponse { address, remaining })
 }
 
-fn query_all_messages(deps: Deps) -> StdResult<MessagesResponse> {
-    let messages: Vec<Message> = MESSAGES
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| item.unwrap().1)
-        .collect();
-    Ok(MessagesResponse { messages })
+fn query_stats(deps: Deps) -> StdResult<StatsResponse> {
+    let stats = STATS.may_load(deps.storage)?.unwrap_or_default();
+    Ok(StatsResponse { stats })
 }
 
-fn query_messages_by_addr(deps: Deps, address: String) -> StdResult<MessagesResponse> {
-    let messages: Vec<Message> = MESSAGES
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| item.unwrap().1)
-        .filter(|message| message.owner == address)
-        .collect();
-    Ok(MessagesResponse { messages })
+fn query_author_stats(deps: Deps, address: String) -> StdResult<AuthorStatsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let stats = AUTHOR_STATS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(AuthorStatsResponse { address, stats })
 }
 
-fn query_messages_by_topic(deps: Deps, topic: String) -> StdResult<MessagesResponse> {
-    let messages: Vec<Message> = MESSAGES
-        .range(deps.storage, None, None, Order::Ascending)
-        .map(|item| item.unwrap().1)
-        .filter(|message| message.topic == topic)
-        .collect();
-    Ok(MessagesResponse { messages })
+// Pages continue after the rank the `start_after` author holds now.
+fn query_top_authors(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TopAuthorsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let likes_received = match &start_after {
+        Some(addr) => AUTHOR_STATS
+            .may_load(deps.storage, addr)?
+            .unwrap_or_default()
+            .likes_received
+            .u128(),
+        None => 0,
+    };
+    let max = start_after
+        .as_ref()
+        .map(|addr| Bound::exclusive((likes_received, addr)));
+
+    let (authors, next_cursor) = page(
+        AUTHOR_RANKING
+            .keys(deps.storage, None, max, Order::Descending)
+            .map(|key| {
+                let (_, address) = key?;
+                let stats = AUTHOR_STATS.load(deps.storage, &address)?;
+                Ok(AuthorStatsResponse { address, stats })
+            }),
+        limit,
+        |author| author.address.clone(),
+    )?;
+    Ok(TopAuthorsResponse {
+        authors,
+        next_cursor,
+    })
 }
 
-fn query_messages_by_id(deps: Deps, id: Uint128) -> StdResult<MessagesResponse> {
-    let message = MESSAGES.load(deps.storage, id.u128())?;
-    Ok(MessagesResponse {
-        messages: vec![message],
+fn query_treasury(deps: Deps) -> StdResult<TreasuryResponse> {
+    let coins = |fees: Map<&str, Uint128>| {
+        fees.range(deps.storage, None, None, Order::Ascending)
+            .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+            .filter(|coin| !matches!(coin, Ok(coin) if coin.amount.is_zero()))
+            .collect::<StdResult<Vec<_>>>()
+    };
+    Ok(TreasuryResponse {
+        treasury: CONFIG.load(deps.storage)?.treasury,
+        available: coins(FEES)?,
+        collected: coins(FEES_COLLECTED)?,
     })
 }
 
@@ -243,28 +2444,26 @@ mod tests {
 
     use super::*;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_binary, coin, Coin};
+    use crate::state::{AuthorStats, DEFAULT_MAX_TAGS, DEFAULT_MAX_TITLE_LENGTH};
+    use cosmwasm_std::{from_binary, coin, Coin, Decimal};
 
     const SENDER: &str = "sender_address";
     const SENDER2: &str = "sender_address2";
+    const SENDER3: &str = "sender_address3";
     const LIKECOIN_DENOM: &str = "like_coin";
     const LIKECOIN_AMOUNT: u128 = 100;
-    const LIKECOIN_WRONG_DENOM: &str = "bad_coin";
     const LIKECOIN_WRONG_AMOUNT: u128 = 50;
 
 
     fn setup_contract(deps: DepsMut) {
-        let msg = InstantiateMsg {stipend: coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)};
+        let msg = InstantiateMsg { post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], ..Default::default() };
         let info = mock_info(SENDER, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
         let res = instantiate(deps, mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
     }
 
     fn add_message(deps: DepsMut, sender: &str, topic: String, message: String) {
-        let msg = ExecuteMsg::AddMessage {
-            topic: topic,
-            message: message,
-        };
+        let msg = ExecuteMsg::AddMessage { topic, message, parent_id: None, tags: None };
         let info = mock_info(sender, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
         execute(deps, mock_env(), info, msg).unwrap();
     }
@@ -312,11 +2511,11 @@ mod tests {
             "message2".to_string(),
         );
 
-        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAllMessage {}).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAllMessage { start_after: None, limit: None, order: None, include_deleted: None }).unwrap();
         let value: MessagesResponse = from_binary(&res).unwrap();
         assert_eq!(2, value.messages.len());
-        assert_eq!(Message{ id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic1".to_string(), message: "message1".to_string() }, value.messages[0]);
-        assert_eq!(Message{ id: Uint128::from(1u128), owner: Addr::unchecked(SENDER), topic: "topic2".to_string(), message: "message2".to_string() }, value.messages[1]);
+        assert_eq!(Message{ id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic1".to_string(), message: "message1".to_string(), revision: 0, edited_at: None, deleted: false, parent_id: None, depth: 0, reply_count: Uint128::zero(), hidden: false, created_at: Some(mock_env().block.time), created_height: Some(mock_env().block.height), tags: vec![] }, value.messages[0]);
+        assert_eq!(Message{ id: Uint128::from(1u128), owner: Addr::unchecked(SENDER), topic: "topic2".to_string(), message: "message2".to_string(), revision: 0, edited_at: None, deleted: false, parent_id: None, depth: 0, reply_count: Uint128::zero(), hidden: false, created_at: Some(mock_env().block.time), created_height: Some(mock_env().block.height), tags: vec![] }, value.messages[1]);
     }
 
     #[test]
@@ -339,7 +2538,7 @@ mod tests {
 
         let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
         like_message(deps.as_mut(), SENDER2,0, funds.clone() );
-        like_message(deps.as_mut(), SENDER2,0, funds );
+        like_message(deps.as_mut(), SENDER3,0, funds );
 
         let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLikesById { id: Uint128::zero() }).unwrap();
         let value: LikesResponse = from_binary(&res).unwrap();
@@ -371,8 +2570,900 @@ mod tests {
         let funds = vec![coin(LIKECOIN_WRONG_AMOUNT, LIKECOIN_DENOM)];
 
         let res : error::ContractError = like_message_with_error_response(deps.as_mut(), SENDER2, 0, funds.clone()).unwrap_err();
-        assert_eq!(res, ContractError::InvalidFundsMessage{val1: LIKECOIN_DENOM.to_string(), val2: LIKECOIN_AMOUNT.to_string()});
+        assert_eq!(res, ContractError::InvalidFundsLike { accepted: coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM).to_string() });
+
+    }
+
+    #[test]
+    fn pay_with_any_accepted_denom() {
+        let mut deps = mock_dependencies();
+        let stipend = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM), coin(LIKECOIN_WRONG_AMOUNT, "other_coin")];
+        let msg = InstantiateMsg { post_price: stipend.clone(), like_price: vec![coin(1, "other_coin"), coin(2, "other_coin")], ..Default::default() };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), msg).unwrap_err();
+        assert_eq!(ContractError::DuplicateDenom { denom: "other_coin".to_string() }, err);
+        // Native denoms can't take the form CW20 payments are booked under
+        let msg = InstantiateMsg { post_price: stipend.clone(), like_price: vec![coin(1, "cw20:token")], ..Default::default() };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), msg).unwrap_err();
+        assert_eq!(ContractError::ReservedDenom { denom: "cw20:token".to_string() }, err);
+        let msg = InstantiateMsg { post_price: stipend.clone(), like_price: stipend.clone(), ..Default::default() };
+        instantiate(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), msg).unwrap();
+        let reactions = vec![Reaction { name: "laugh".to_string(), price: coin(1, "cw20:token") }];
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { reactions: Some(reactions), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap_err();
+        assert_eq!(ContractError::ReservedDenom { denom: "cw20:token".to_string() }, err);
+
+        let add = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+        let info = mock_info(SENDER, &[coin(LIKECOIN_WRONG_AMOUNT, "other_coin")]);
+        execute(deps.as_mut(), mock_env(), info, add.clone()).unwrap();
+        // Paying with every accepted coin at once is rejected
+        let info = mock_info(SENDER, &stipend);
+        let err = execute(deps.as_mut(), mock_env(), info, add).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsMessage { accepted: "100like_coin, 50other_coin".to_string() }, err);
+
+        // The author receives the like in the denom it was paid with
+        let like = ExecuteMsg::LikeMessage { id: Uint128::zero() };
+        let info = mock_info(SENDER2, &[coin(LIKECOIN_WRONG_AMOUNT, "other_coin")]);
+        let res = execute(deps.as_mut(), mock_env(), info, like).unwrap();
+        assert_eq!(res.messages[0].msg, BankMsg::Send { to_address: SENDER.to_string(), amount: vec![coin(LIKECOIN_WRONG_AMOUNT, "other_coin")] }.into());
+    }
+
+    #[test]
+    fn fees_are_booked_and_withdrawn_by_admin() {
+        let mut deps = mock_dependencies();
+        let price = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        let msg = InstantiateMsg { post_price: price.clone(), like_price: price.clone(), fee_bps: Some(2_500), ..Default::default() };
+        instantiate(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), msg).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        like_message(deps.as_mut(), SENDER2, 0, price);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTreasury {}).unwrap();
+        let value: TreasuryResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![coin(125, LIKECOIN_DENOM)], value.available);
+        assert_eq!(vec![coin(125, LIKECOIN_DENOM)], value.collected);
+
+        let withdraw = |amount: u128| ExecuteMsg::WithdrawFees { to: SENDER3.to_string(), amount: vec![coin(amount, LIKECOIN_DENOM)] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), withdraw(100)).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), withdraw(200)).unwrap_err();
+        assert_eq!(ContractError::InsufficientFees { denom: LIKECOIN_DENOM.to_string(), available: Uint128::new(125) }, err);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), withdraw(0)).unwrap_err();
+        assert_eq!(ContractError::InvalidWithdrawal {}, err);
+        let empty = ExecuteMsg::WithdrawFees { to: SENDER3.to_string(), amount: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), empty).unwrap_err();
+        assert_eq!(ContractError::InvalidWithdrawal {}, err);
+        let fake_token = ExecuteMsg::WithdrawFees { to: SENDER3.to_string(), amount: vec![coin(10, "cw20:x")] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), fake_token).unwrap_err();
+        assert_eq!(ContractError::InvalidWithdrawal {}, err);
+
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), withdraw(100)).unwrap();
+        assert_eq!(res.messages[0].msg, BankMsg::Send { to_address: SENDER3.to_string(), amount: vec![coin(100, LIKECOIN_DENOM)] }.into());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTreasury {}).unwrap();
+        let value: TreasuryResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![coin(25, LIKECOIN_DENOM)], value.available);
+        assert_eq!(vec![coin(125, LIKECOIN_DENOM)], value.collected);
+
+        // Unliking refunds the fee share, or what is left of it
+        let unlike = ExecuteMsg::UnlikeMessage { id: Uint128::zero() };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), unlike.clone()).unwrap();
+        assert_eq!(res.messages[0].msg, BankMsg::Send { to_address: SENDER2.to_string(), amount: vec![coin(25, LIKECOIN_DENOM)] }.into());
+        like_message(deps.as_mut(), SENDER3, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), withdraw(10)).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), unlike).unwrap();
+        assert_eq!(res.messages[0].msg, BankMsg::Send { to_address: SENDER3.to_string(), amount: vec![coin(15, LIKECOIN_DENOM)] }.into());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTreasury {}).unwrap();
+        let value: TreasuryResponse = from_binary(&res).unwrap();
+        assert!(value.available.is_empty());
+        assert_eq!(vec![coin(110, LIKECOIN_DENOM)], value.collected);
+    }
+
+    #[test]
+    fn free_posts_need_a_sponsored_quota() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let post = ExecuteMsg::AddMessageWithoutFunds { topic: "topic1".to_string(), message: "message1".to_string(), tags: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), post.clone()).unwrap_err();
+        assert_eq!(ContractError::NotSponsored {}, err);
+
+        let grant = ExecuteMsg::GrantSponsoredPosts { address: SENDER2.to_string(), quota: 2 };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), grant.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), grant).unwrap();
+
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), post.clone()).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetSponsoredQuota { address: SENDER2.to_string() }).unwrap();
+        let value: SponsoredQuotaResponse = from_binary(&res).unwrap();
+        assert_eq!(1, value.remaining);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), post.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), post).unwrap_err();
+        assert_eq!(ContractError::NotSponsored {}, err);
+    }
+
+    #[test]
+    fn like_once_per_address_and_unlike() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic2".to_string(), "message2".to_string());
+
+        let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        like_message(deps.as_mut(), SENDER3, 0, funds.clone());
+        like_message(deps.as_mut(), SENDER2, 0, funds.clone());
+        like_message(deps.as_mut(), SENDER2, 1, funds.clone());
+        let res = like_message_with_error_response(deps.as_mut(), SENDER2, 0, funds).unwrap_err();
+        assert_eq!(ContractError::AlreadyLiked {}, res);
+
+        let msg = QueryMsg::GetLikers { id: Uint128::zero(), start_after: None, limit: Some(1) };
+        let value: LikersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![Addr::unchecked(SENDER2)], value.likers);
+        let msg = QueryMsg::GetLikers { id: Uint128::zero(), start_after: value.next_cursor.map(String::from), limit: Some(1) };
+        let value: LikersResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(LikersResponse { likers: vec![Addr::unchecked(SENDER3)], next_cursor: None }, value);
+
+        let msg = QueryMsg::GetLikedMessages { address: SENDER2.to_string(), start_after: None, limit: None, order: Some(OrderBy::Desc), include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(1u128), Uint128::zero()], ids);
+
+        let unlike = ExecuteMsg::UnlikeMessage { id: Uint128::zero() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), unlike.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), unlike).unwrap_err();
+        assert_eq!(ContractError::NotLiked {}, err);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLikesById { id: Uint128::zero() }).unwrap();
+        let value: LikesResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(1u128), value.likes.count);
+
+        // After unliking, the address can like the message again
+        like_message(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+    }
+
+    #[test]
+    fn edit_message_keeps_history() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "message1 edited".to_string(), topic: "topic2".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), edit.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        let missing = ExecuteMsg::EditMessage { id: Uint128::from(7u128), message: "message".to_string(), topic: "topic".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), missing).unwrap_err();
+        assert_eq!(ContractError::InvalidMessageID {}, err);
+
+        let env = mock_env();
+        execute(deps.as_mut(), env.clone(), mock_info(SENDER, &[]), edit).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMessagesById { id: Uint128::zero() }).unwrap();
+        let value: MessagesResponse = from_binary(&res).unwrap();
+        let edited = Message { id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic2".to_string(), message: "message1 edited".to_string(), revision: 1, edited_at: Some(env.block.time), deleted: false, parent_id: None, depth: 0, reply_count: Uint128::zero(), hidden: false, created_at: Some(mock_env().block.time), created_height: Some(mock_env().block.height), tags: vec![] };
+        assert_eq!(vec![edited.clone()], value.messages);
+
+        // The topic index follows the edit
+        let msg = QueryMsg::GetMessagesByTopic { topic: "topic1".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(value.messages.is_empty());
+        let msg = QueryMsg::GetMessagesByTopic { topic: "topic2".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![edited], value.messages);
+
+        let msg = QueryMsg::GetMessageHistory { id: Uint128::zero(), start_after: None, limit: None };
+        let value: MessageHistoryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let original = Message { id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic1".to_string(), message: "message1".to_string(), revision: 0, edited_at: None, deleted: false, parent_id: None, depth: 0, reply_count: Uint128::zero(), hidden: false, created_at: Some(mock_env().block.time), created_height: Some(mock_env().block.height), tags: vec![] };
+        assert_eq!(MessageHistoryResponse { revisions: vec![original], next_cursor: None }, value);
+    }
+
+    #[test]
+    fn delete_message_leaves_tombstone() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER2, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER2, "topic1".to_string(), "message2".to_string());
+        like_message(deps.as_mut(), SENDER3, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "message1 edited".to_string(), topic: "topic1".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), edit.clone()).unwrap();
+
+        let delete = ExecuteMsg::DeleteMessage { id: Uint128::zero() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), delete.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        // SENDER is the admin, the owner can delete too
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), delete.clone()).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), ExecuteMsg::DeleteMessage { id: Uint128::from(1u128) }).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), delete).unwrap_err();
+        assert_eq!(ContractError::MessageDeleted {}, err);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), edit).unwrap_err();
+        assert_eq!(ContractError::MessageDeleted {}, err);
+        let err = like_message_with_error_response(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]).unwrap_err();
+        assert_eq!(ContractError::MessageDeleted {}, err);
+
+        let msg = QueryMsg::GetMessagesByAddr { address: SENDER2.to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(value.messages.is_empty());
+
+        let msg = QueryMsg::GetAllMessage { start_after: None, limit: Some(1), order: None, include_deleted: Some(true) };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(1, value.messages.len());
+        assert!(value.messages[0].deleted);
+        assert_eq!("", value.messages[0].message);
+        assert_eq!(Some(Uint128::zero()), value.next_cursor);
+
+        // The like count and the id slot survive, the edit history does not
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLikesById { id: Uint128::zero() }).unwrap();
+        let value: LikesResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128::from(1u128), value.likes.count);
+        let msg = QueryMsg::GetMessageHistory { id: Uint128::zero(), start_after: None, limit: None };
+        let value: MessageHistoryResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert!(value.revisions.is_empty());
+    }
+
+    #[test]
+    fn reply_to_messages_and_query_thread() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { max_thread_depth: Some(2), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "root".to_string());
+
+        let reply = |deps: DepsMut, parent_id: u128, message: &str| {
+            let msg = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: message.to_string(), parent_id: Some(Uint128::from(parent_id)), tags: None };
+            let info = mock_info(SENDER2, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+            execute(deps, mock_env(), info, msg)
+        };
+        reply(deps.as_mut(), 0, "reply 1").unwrap();
+        reply(deps.as_mut(), 0, "reply 2").unwrap();
+        reply(deps.as_mut(), 1, "reply 1.1").unwrap();
+        let err = reply(deps.as_mut(), 3, "reply 1.1.1").unwrap_err();
+        assert_eq!(ContractError::MaxThreadDepthExceeded { max_depth: 2 }, err);
+        let err = reply(deps.as_mut(), 9, "reply to nothing").unwrap_err();
+        assert_eq!(ContractError::InvalidMessageID {}, err);
+
+        let msg = QueryMsg::GetReplies { id: Uint128::zero(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(1u128), Uint128::from(2u128)], ids);
+        assert_eq!(Some(Uint128::zero()), value.messages[0].parent_id);
+        assert_eq!(1, value.messages[0].depth);
+        assert_eq!(Uint128::from(1u128), value.messages[0].reply_count);
+
+        let msg = QueryMsg::GetThread { root_id: Uint128::zero(), start_after: None, limit: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<u128> = value.messages.iter().map(|m| m.id.u128()).collect();
+        assert_eq!(vec![0, 1, 3, 2], ids);
+        assert_eq!(Uint128::from(2u128), value.messages[0].reply_count);
+
+        // Long threads come in pages that resume the walk where the previous one stopped
+        let msg = QueryMsg::GetThread { root_id: Uint128::zero(), start_after: None, limit: Some(3) };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<u128> = value.messages.iter().map(|m| m.id.u128()).collect();
+        assert_eq!(vec![0, 1, 3], ids);
+        assert_eq!(Some(Uint128::from(3u128)), value.next_cursor);
+        let msg = QueryMsg::GetThread { root_id: Uint128::zero(), start_after: value.next_cursor, limit: Some(3) };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<u128> = value.messages.iter().map(|m| m.id.u128()).collect();
+        assert_eq!(vec![2], ids);
+        assert_eq!(None, value.next_cursor);
+        let msg = QueryMsg::GetThread { root_id: Uint128::new(1), start_after: None, limit: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<u128> = value.messages.iter().map(|m| m.id.u128()).collect();
+        assert_eq!(vec![1, 3], ids);
+        // A cursor from another thread is refused
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "other root".to_string());
+        reply(deps.as_mut(), 4, "other reply").unwrap();
+        let msg = QueryMsg::GetThread { root_id: Uint128::zero(), start_after: Some(Uint128::new(4)), limit: None };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+        let msg = QueryMsg::GetThread { root_id: Uint128::new(1), start_after: Some(Uint128::new(2)), limit: None };
+        query(deps.as_ref(), mock_env(), msg).unwrap_err();
+
+        // Lowering the max depth also trims the thread query
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { max_thread_depth: Some(1), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        let msg = QueryMsg::GetThread { root_id: Uint128::zero(), start_after: None, limit: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<u128> = value.messages.iter().map(|m| m.id.u128()).collect();
+        assert_eq!(vec![0, 1, 2], ids);
+    }
+
+    #[test]
+    fn query_messages_by_addr_and_topic() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER2, "topic2".to_string(), "message2".to_string());
+        add_message(deps.as_mut(), SENDER2, "topic1".to_string(), "message3".to_string());
+
+        let msg = QueryMsg::GetMessagesByAddr { address: SENDER2.to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(1u128), Uint128::from(2u128)], ids);
+
+        let msg = QueryMsg::GetMessagesByTopic { topic: "topic1".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::zero(), Uint128::from(2u128)], ids);
+    }
+
+    #[test]
+    fn query_messages_between_times() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let start = mock_env().block.time;
+        for i in 0..3u64 {
+            let mut env = mock_env();
+            env.block.time = start.plus_seconds(10 * i);
+            env.block.height += i;
+            let msg = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: format!("message{}", i), parent_id: None, tags: None };
+            execute(deps.as_mut(), env, mock_info(SENDER, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]), msg).unwrap();
+        }
+
+        let between = |start_after: Option<Uint128>, order: Option<OrderBy>| QueryMsg::GetMessagesBetween { start: start.plus_seconds(5), end: start.plus_seconds(25), start_after, limit: Some(1), order, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), between(None, None)).unwrap()).unwrap();
+        assert_eq!(Uint128::from(1u128), value.messages[0].id);
+        assert_eq!(Some(start.plus_seconds(10)), value.messages[0].created_at);
+        assert_eq!(Some(mock_env().block.height + 1), value.messages[0].created_height);
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), between(value.next_cursor, None)).unwrap()).unwrap();
+        assert_eq!(Uint128::from(2u128), value.messages[0].id);
+        assert_eq!(None, value.next_cursor);
+
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), between(None, Some(OrderBy::Desc))).unwrap()).unwrap();
+        assert_eq!(Uint128::from(2u128), value.messages[0].id);
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), between(value.next_cursor, Some(OrderBy::Desc))).unwrap()).unwrap();
+        assert_eq!(Uint128::from(1u128), value.messages[0].id);
+        assert_eq!(None, value.next_cursor);
+
+        // A cursor from before or after the range doesn't stretch it
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), between(Some(Uint128::zero()), None)).unwrap()).unwrap();
+        assert_eq!(Uint128::from(1u128), value.messages[0].id);
+        let msg = QueryMsg::GetMessagesBetween { start: start.plus_seconds(15), end: start.plus_seconds(100), start_after: Some(Uint128::zero()), limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(2u128)], ids);
+        let msg = QueryMsg::GetMessagesBetween { start: start.plus_seconds(0), end: start.plus_seconds(15), start_after: Some(Uint128::from(2u128)), limit: None, order: Some(OrderBy::Desc), include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(1u128), Uint128::zero()], ids);
+    }
+
+    #[test]
+    fn query_top_messages_by_likes() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic2".to_string(), "message2".to_string());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message3".to_string());
+        like_message(deps.as_mut(), SENDER2, 1, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER3, 1, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER2, 2, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+
+        let top = |deps: Deps, topic: Option<&str>| {
+            let msg = QueryMsg::GetTopMessages { topic: topic.map(str::to_string), limit: None };
+            let value: TopMessagesResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            value.messages.into_iter().map(|ranked| (ranked.message.id.u128(), ranked.likes.u128())).collect::<Vec<_>>()
+        };
+        // Messages without likes are not ranked
+        assert_eq!(vec![(1, 2), (2, 1)], top(deps.as_ref(), None));
+        assert_eq!(vec![(2, 1)], top(deps.as_ref(), Some("topic1")));
+
+        // Unliking moves a message down
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::new(1) }).unwrap();
+        like_message(deps.as_mut(), SENDER3, 2, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        assert_eq!(vec![(2, 2), (1, 1)], top(deps.as_ref(), None));
+
+        // Moving a message to another topic moves its rank along
+        let edit = ExecuteMsg::EditMessage { id: Uint128::new(2), message: "message3".to_string(), topic: "topic2".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), edit).unwrap();
+        assert_eq!(Vec::<(u128, u128)>::new(), top(deps.as_ref(), Some("topic1")));
+        assert_eq!(vec![(2, 2), (1, 1)], top(deps.as_ref(), Some("topic2")));
+
+        // Deleted messages drop out of the leaderboard
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), ExecuteMsg::DeleteMessage { id: Uint128::new(2) }).unwrap();
+        assert_eq!(vec![(1, 1)], top(deps.as_ref(), None));
+    }
+
+    #[test]
+    fn trending_score_decays_with_height() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { trending_half_life: Some(0), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap_err();
+        assert_eq!(ContractError::InvalidHalfLife {}, err);
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { trending_half_life: Some(100), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic2".to_string(), "message2".to_string());
+
+        // Two likes on the first message now, one on the second two half-lives later
+        let like = |id: u128| ExecuteMsg::LikeMessage { id: Uint128::new(id) };
+        let funds = [coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &funds), like(0)).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &funds), like(0)).unwrap();
+        let mut env = mock_env();
+        env.block.height += 200;
+        execute(deps.as_mut(), env.clone(), mock_info(SENDER2, &funds), like(1)).unwrap();
+
+        let trending = |deps: Deps, topic: Option<&str>| {
+            let msg = QueryMsg::GetTrending { topic: topic.map(str::to_string), limit: None };
+            let value: TrendingResponse = from_binary(&query(deps, env.clone(), msg).unwrap()).unwrap();
+            value.messages.into_iter().map(|trending| (trending.message.id.u128(), trending.score)).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![(1, Decimal::one()), (0, Decimal::percent(50))], trending(deps.as_ref(), None));
+        assert_eq!(vec![(0, Decimal::percent(50))], trending(deps.as_ref(), Some("topic1")));
+
+        // Stored scores are scaled by the half-life, which can't change under them anymore
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { trending_half_life: Some(1000), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap_err();
+        assert_eq!(ContractError::HalfLifeLocked {}, err);
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { trending_half_life: Some(100), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+
+        // Unliking takes the weight of that like away, the last one removes the message
+        execute(deps.as_mut(), env.clone(), mock_info(SENDER3, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::zero() }).unwrap();
+        assert_eq!(vec![(1, Decimal::one()), (0, Decimal::percent(25))], trending(deps.as_ref(), None));
+        execute(deps.as_mut(), env.clone(), mock_info(SENDER2, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::zero() }).unwrap();
+        assert_eq!(vec![(1, Decimal::one())], trending(deps.as_ref(), None));
+        assert_eq!(Vec::<(u128, Decimal)>::new(), trending(deps.as_ref(), Some("topic1")));
+    }
+
+    #[test]
+    fn author_stats_and_top_authors() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { fee_bps: Some(1000), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message2".to_string());
+        add_message(deps.as_mut(), SENDER2, "topic1".to_string(), "message3".to_string());
+        like_message(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER2, 1, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER3, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER, 2, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::zero() }).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAuthorStats { address: SENDER.to_string() }).unwrap();
+        let value: AuthorStatsResponse = from_binary(&res).unwrap();
+        let sender = AuthorStats { messages_posted: Uint128::new(2), likes_received: Uint128::new(2), likes_given: Uint128::new(1), earned: vec![coin(270, LIKECOIN_DENOM)], spent: vec![coin(300, LIKECOIN_DENOM)] };
+        assert_eq!(sender, value.stats);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAuthorStats { address: SENDER3.to_string() }).unwrap();
+        let value: AuthorStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(AuthorStats { spent: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], ..Default::default() }, value.stats);
+
+        // Only addresses that posted are ranked
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListTopAuthors { start_after: None, limit: Some(1) }).unwrap();
+        let value: TopAuthorsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![AuthorStatsResponse { address: Addr::unchecked(SENDER), stats: sender }], value.authors);
+        assert_eq!(Some(Addr::unchecked(SENDER)), value.next_cursor);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListTopAuthors { start_after: Some(SENDER.to_string()), limit: Some(1) }).unwrap();
+        let value: TopAuthorsResponse = from_binary(&res).unwrap();
+        let sender2 = AuthorStats { messages_posted: Uint128::new(1), likes_received: Uint128::new(1), likes_given: Uint128::new(2), earned: vec![coin(90, LIKECOIN_DENOM)], spent: vec![coin(300, LIKECOIN_DENOM)] };
+        assert_eq!(vec![AuthorStatsResponse { address: Addr::unchecked(SENDER2), stats: sender2 }], value.authors);
+        assert_eq!(None, value.next_cursor);
+    }
+
+    #[test]
+    fn board_stats() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap();
+        let value: StatsResponse = from_binary(&res).unwrap();
+        assert_eq!(Stats::default(), value.stats);
+
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message2".to_string());
+        add_message(deps.as_mut(), SENDER2, "topic1".to_string(), "message3".to_string());
+        like_message(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        like_message(deps.as_mut(), SENDER3, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::zero() }).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), ExecuteMsg::DeleteMessage { id: Uint128::new(1) }).unwrap();
+
+        // Unlikes and deletes don't lower the payment volume
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap();
+        let value: StatsResponse = from_binary(&res).unwrap();
+        assert_eq!(Stats { total_messages: Uint128::new(3), live_messages: Uint128::new(2), total_likes: Uint128::new(1), authors: Uint128::new(2), payment_volume: vec![coin(5 * LIKECOIN_AMOUNT, LIKECOIN_DENOM)] }, value.stats);
+    }
+
+    #[test]
+    fn content_rules_and_topic_normalization() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let rules = ContentRules { min_message_length: 3, max_message_length: 10, max_topic_length: 8, ..Default::default() };
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { content_rules: Some(ContentRules { min_message_length: 11, ..rules.clone() }), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap_err();
+        assert_eq!(ContractError::InvalidContentRules {}, err);
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { content_rules: Some(rules), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+
+        let add = |topic: &str, message: &str| ExecuteMsg::AddMessage { topic: topic.to_string(), message: message.to_string(), parent_id: None, tags: None };
+        let funds = [coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        let cases = [
+            ("topic1", "  a  ", ContractError::MessageTooShort { min_length: 3 }),
+            ("topic1", "message too long", ContractError::MessageTooLong { max_length: 10 }),
+            ("   ", "message1", ContractError::EmptyTopic {}),
+            ("longtopic", "message1", ContractError::TopicTooLong { max_length: 8 }),
+            ("my topic", "message1", ContractError::InvalidTopicCharacter { character: ' ' }),
+        ];
+        for (topic, message, expected) in cases {
+            let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add(topic, message)).unwrap_err();
+            assert_eq!(expected, err);
+        }
+
+        // Topics are trimmed and lowercased when posting and when querying
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add(" Topic1 ", "message1")).unwrap();
+        let msg = QueryMsg::GetMessagesByTopic { topic: "TOPIC1".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!("topic1", value.messages[0].topic);
+
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "x".to_string(), topic: "topic1".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), edit).unwrap_err();
+        assert_eq!(ContractError::MessageTooShort { min_length: 3 }, err);
+
+        // Topic details and report reasons are bounded too
+        let create = |title: &str, description: &str| ExecuteMsg::CreateTopic { name: "topic2".to_string(), title: title.to_string(), description: description.to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), create(&"t".repeat(DEFAULT_MAX_TITLE_LENGTH as usize + 1), "about")).unwrap_err();
+        assert_eq!(ContractError::TitleTooLong { max_length: DEFAULT_MAX_TITLE_LENGTH }, err);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), create("Topic 2", "description too long")).unwrap_err();
+        assert_eq!(ContractError::DescriptionTooLong { max_length: 10 }, err);
+        let report = ExecuteMsg::ReportMessage { id: Uint128::zero(), reason: "reason too long".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), report).unwrap_err();
+        assert_eq!(ContractError::ReasonTooLong { max_length: 10 }, err);
+    }
+
+    #[test]
+    fn tags_index_and_queries() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let add = |tags: &[&str]| ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: Some(tags.iter().map(|tag| tag.to_string()).collect()) };
+        let funds = [coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        let cases = [
+            (add(&["a", "b", "c", "d", "e", "f"]), ContractError::TooManyTags { max_tags: DEFAULT_MAX_TAGS }),
+            (add(&["a", " A"]), ContractError::DuplicateTag { tag: "a".to_string() }),
+            (add(&["a b"]), ContractError::InvalidTagCharacter { character: ' ' }),
+        ];
+        for (msg, expected) in cases {
+            let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), msg).unwrap_err();
+            assert_eq!(expected, err);
+        }
+
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add(&["Rust", "wasm"])).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add(&["rust"])).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add(&["go", "wasm"])).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+
+        let ids = |deps: Deps, msg: QueryMsg| {
+            let value: MessagesResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            (value.messages.iter().map(|m| m.id.u128()).collect::<Vec<_>>(), value.next_cursor)
+        };
+        let by_tags = |tags: &[&str], mode: TagMatch, start_after: Option<u128>, limit: Option<u32>, order: Option<OrderBy>| QueryMsg::GetMessagesByTags { tags: tags.iter().map(|tag| tag.to_string()).collect(), mode, start_after: start_after.map(Uint128::new), limit, order, include_deleted: None };
+        let msg = QueryMsg::GetMessagesByTag { tag: "RUST".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        assert_eq!((vec![0, 1], None), ids(deps.as_ref(), msg));
+        assert_eq!((vec![0], None), ids(deps.as_ref(), by_tags(&["rust", "wasm"], TagMatch::All, None, None, None)));
+        assert_eq!((vec![2, 1, 0], None), ids(deps.as_ref(), by_tags(&["rust", "go"], TagMatch::Any, None, None, Some(OrderBy::Desc))));
+        assert_eq!((vec![0, 1], Some(Uint128::new(1))), ids(deps.as_ref(), by_tags(&["rust", "go", "wasm"], TagMatch::Any, None, Some(2), None)));
+        assert_eq!((vec![2], None), ids(deps.as_ref(), by_tags(&["rust", "go", "wasm"], TagMatch::Any, Some(1), Some(2), None)));
+
+        // Counts for autocompletion leave deleted messages out
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), ExecuteMsg::DeleteMessage { id: Uint128::new(1) }).unwrap();
+        let tags = |deps: Deps, prefix: Option<&str>| {
+            let msg = QueryMsg::ListTags { prefix: prefix.map(str::to_string), start_after: None, limit: None };
+            let value: TagsResponse = from_binary(&query(deps, mock_env(), msg).unwrap()).unwrap();
+            value.tags.into_iter().map(|tag| (tag.tag, tag.count)).collect::<Vec<_>>()
+        };
+        assert_eq!(vec![("go".to_string(), 1), ("rust".to_string(), 1), ("wasm".to_string(), 2)], tags(deps.as_ref(), None));
+        assert_eq!(vec![("rust".to_string(), 1)], tags(deps.as_ref(), Some("R")));
+    }
+
+    #[test]
+    fn paginate_messages_in_both_orders() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        for i in 0..(MAX_LIMIT + 5) {
+            add_message(deps.as_mut(), SENDER, "topic1".to_string(), format!("message{}", i));
+        }
+
+        // The limit defaults to DEFAULT_LIMIT and the cursor points at the last returned id
+        let msg = QueryMsg::GetAllMessage { start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(DEFAULT_LIMIT as usize, value.messages.len());
+        assert_eq!(Some(Uint128::from(DEFAULT_LIMIT as u128 - 1)), value.next_cursor);
+
+        // Limits above MAX_LIMIT are capped
+        let msg = QueryMsg::GetMessagesByTopic { topic: "topic1".to_string(), start_after: value.next_cursor, limit: Some(100), order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(MAX_LIMIT as usize - 5, value.messages.len());
+        assert_eq!(Uint128::from(DEFAULT_LIMIT as u128), value.messages[0].id);
+        assert_eq!(None, value.next_cursor);
+
+        let msg = QueryMsg::GetMessagesByAddr { address: SENDER.to_string(), start_after: Some(Uint128::from(3u128)), limit: Some(2), order: Some(OrderBy::Desc), include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        let ids: Vec<Uint128> = value.messages.iter().map(|m| m.id).collect();
+        assert_eq!(vec![Uint128::from(2u128), Uint128::from(1u128)], ids);
+        assert_eq!(Some(Uint128::from(1u128)), value.next_cursor);
+    }
+
+    #[test]
+    fn reports_hide_messages_until_moderated() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { auto_hide_reports: Some(2), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "message1 edited".to_string(), topic: "topic1".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), edit).unwrap();
+
+        let report = ExecuteMsg::ReportMessage { id: Uint128::zero(), reason: "spam".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), report.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), report.clone()).unwrap_err();
+        assert_eq!(ContractError::AlreadyReported {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), report.clone()).unwrap();
+
+        // Two reporters reached the threshold, so the message is out of listings and can't be liked
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetAllMessage { start_after: None, limit: None, order: None, include_deleted: None }).unwrap();
+        let value: MessagesResponse = from_binary(&res).unwrap();
+        assert!(value.messages.is_empty());
+        let err = like_message_with_error_response(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]).unwrap_err();
+        assert_eq!(ContractError::MessageHidden {}, err);
+        // Queries still return the hidden message where it belongs, but not its text
+        let queries = [
+            QueryMsg::GetThread { root_id: Uint128::zero(), start_after: None, limit: None },
+            QueryMsg::GetMessagesById { id: Uint128::zero() },
+            QueryMsg::GetAllMessage { start_after: None, limit: None, order: None, include_deleted: Some(true) },
+        ];
+        for msg in queries {
+            let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+            assert!(value.messages[0].hidden);
+            assert_eq!("", value.messages[0].message);
+        }
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMessageHistory { id: Uint128::zero(), start_after: None, limit: None }).unwrap();
+        let value: MessageHistoryResponse = from_binary(&res).unwrap();
+        assert_eq!("", value.revisions[0].message);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetModerationQueue { start_after: None, limit: None }).unwrap();
+        let value: ModerationQueueResponse = from_binary(&res).unwrap();
+        assert_eq!(2, value.messages[0].report_count);
+        assert!(value.messages[0].message.hidden);
+        assert_eq!("message1 edited", value.messages[0].message.message);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetReports { id: Uint128::zero(), start_after: None, limit: None }).unwrap();
+        let value: ReportsResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![Report { reporter: Addr::unchecked(SENDER2), reason: "spam".to_string() }, Report { reporter: Addr::unchecked(SENDER3), reason: "spam".to_string() }], value.reports);
+
+        // Only the admin manages moderators, and only moderators restore messages
+        let restore = ExecuteMsg::RestoreMessage { id: Uint128::zero() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), restore.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        let moderators = ExecuteMsg::UpdateModerators { add: vec![SENDER3.to_string()], remove: vec![] };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), moderators.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), moderators).unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), restore).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetModerationQueue { start_after: None, limit: None }).unwrap();
+        let value: ModerationQueueResponse = from_binary(&res).unwrap();
+        assert!(value.messages.is_empty());
+        // Restoring dismissed the reports, so the message can be reported again
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), report).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetMessagesById { id: Uint128::zero() }).unwrap();
+        let value: MessagesResponse = from_binary(&res).unwrap();
+        assert!(!value.messages[0].hidden);
+        assert_eq!("message1 edited", value.messages[0].message);
+    }
+
+    #[test]
+    fn banned_addresses_cannot_post_or_like() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        let until_height = mock_env().block.height + 10;
+
+        let ban = ExecuteMsg::Ban { address: SENDER2.to_string(), until_height: Some(until_height) };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &[]), ban.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), ban).unwrap();
+        let ban = ExecuteMsg::Ban { address: SENDER3.to_string(), until_height: None };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), ban).unwrap();
+
+        let err = like_message_with_error_response(deps.as_mut(), SENDER2, 0, funds.clone()).unwrap_err();
+        assert_eq!(ContractError::Banned { until_height: Some(until_height) }, err);
+        let add = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message2".to_string(), parent_id: None, tags: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER3, &funds), add).unwrap_err();
+        assert_eq!(ContractError::Banned { until_height: None }, err);
+        let reactions = vec![Reaction { name: "laugh".to_string(), price: coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM) }];
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { reactions: Some(reactions), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        let react = ExecuteMsg::React { id: Uint128::zero(), reaction: "laugh".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &funds), react).unwrap_err();
+        assert_eq!(ContractError::Banned { until_height: Some(until_height) }, err);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListBans { start_after: None, limit: None }).unwrap();
+        let value: BansResponse = from_binary(&res).unwrap();
+        assert_eq!(Ban { address: Addr::unchecked(SENDER2), until_height: Some(until_height), banned_by: Addr::unchecked(SENDER) }, value.bans[0]);
+        assert_eq!(2, value.bans.len());
+
+        // The first ban runs out by itself, the second one has to be lifted
+        let mut env = mock_env();
+        env.block.height = until_height;
+        execute(deps.as_mut(), env, mock_info(SENDER2, &funds), ExecuteMsg::LikeMessage { id: Uint128::zero() }).unwrap();
+        let unban = ExecuteMsg::Unban { address: SENDER3.to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), unban).unwrap();
+        like_message(deps.as_mut(), SENDER3, 0, funds);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListBans { start_after: None, limit: None }).unwrap();
+        let value: BansResponse = from_binary(&res).unwrap();
+        assert!(value.bans.is_empty());
+    }
+
+    #[test]
+    fn posts_and_likes_are_rate_limited_per_window() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let rate_limit = RateLimit { window_blocks: 10, max_posts: 2, max_likes: 1 };
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { rate_limit: Some(rate_limit), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        let height = mock_env().block.height;
+
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "topic1".to_string(), "message2".to_string());
+        let add = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message3".to_string(), parent_id: None, tags: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &funds), add.clone()).unwrap_err();
+        assert_eq!(ContractError::RateLimited { action: "posts".to_string(), limit: 2, window_blocks: 10, reset_height: height + 10 }, err);
+
+        // Likes have their own quota
+        like_message(deps.as_mut(), SENDER, 0, funds.clone());
+        let err = like_message_with_error_response(deps.as_mut(), SENDER, 1, funds.clone()).unwrap_err();
+        assert_eq!(ContractError::RateLimited { action: "likes".to_string(), limit: 1, window_blocks: 10, reset_height: height + 10 }, err);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetRateLimitQuota { address: SENDER.to_string() }).unwrap();
+        let value: RateLimitQuotaResponse = from_binary(&res).unwrap();
+        assert_eq!(RateLimitQuotaResponse { posts_left: Some(0), likes_left: Some(0), reset_height: Some(height + 10) }, value);
+
+        // A new window starts once the last one is over
+        let mut env = mock_env();
+        env.block.height = height + 10;
+        execute(deps.as_mut(), env.clone(), mock_info(SENDER, &funds), add).unwrap();
+        let res = query(deps.as_ref(), env, QueryMsg::GetRateLimitQuota { address: SENDER.to_string() }).unwrap();
+        let value: RateLimitQuotaResponse = from_binary(&res).unwrap();
+        assert_eq!(RateLimitQuotaResponse { posts_left: Some(1), likes_left: Some(1), reset_height: Some(height + 20) }, value);
+    }
+
+    #[test]
+    fn registered_topics_count_messages_and_likes() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { strict_topics: Some(true), topic_creation: Some(TopicCreation::AdminOnly), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+
+        let add = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]), add.clone()).unwrap_err();
+        assert_eq!(ContractError::UnknownTopic { topic: "topic1".to_string() }, err);
+
+        let create = |name: &str| ExecuteMsg::CreateTopic { name: name.to_string(), title: "Topic".to_string(), description: "About things".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), create("topic1")).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), create("topic1")).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), create("topic1")).unwrap_err();
+        assert_eq!(ContractError::TopicExists { topic: "topic1".to_string() }, err);
+
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]), add).unwrap();
+        like_message(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "message1".to_string(), topic: "topic2".to_string() };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), edit).unwrap_err();
+        assert_eq!(ContractError::UnknownTopic { topic: "topic2".to_string() }, err);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTopic { name: "topic1".to_string() }).unwrap();
+        let value: TopicResponse = from_binary(&res).unwrap();
+        assert_eq!(Topic { name: "topic1".to_string(), title: "Topic".to_string(), description: "About things".to_string(), creator: Addr::unchecked(SENDER), message_count: Uint128::new(1), like_count: Uint128::new(1) }, value.topic);
+
+        // With a price, anyone can register a topic by paying it
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg { topic_creation: Some(TopicCreation::Anyone), topic_price: Some(vec![coin(10, LIKECOIN_DENOM)]), ..Default::default() });
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), update).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), create("topic0")).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsTopic { accepted: "10like_coin".to_string() }, err);
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[coin(10, LIKECOIN_DENOM)]), create("topic0")).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListTopics { start_after: None, limit: Some(1) }).unwrap();
+        let value: TopicsResponse = from_binary(&res).unwrap();
+        assert_eq!("topic0", value.topics[0].name);
+        assert_eq!(Some("topic0".to_string()), value.next_cursor);
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::ListTopics { start_after: value.next_cursor, limit: Some(1) }).unwrap();
+        let value: TopicsResponse = from_binary(&res).unwrap();
+        assert_eq!("topic1", value.topics[0].name);
+        assert_eq!(None, value.next_cursor);
+    }
+
+    #[test]
+    fn registering_a_used_topic_counts_its_messages() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        add_message(deps.as_mut(), SENDER, "foo".to_string(), "message1".to_string());
+        add_message(deps.as_mut(), SENDER, "foo".to_string(), "message2".to_string());
+        like_message(deps.as_mut(), SENDER2, 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        let create = ExecuteMsg::CreateTopic { name: "foo".to_string(), title: "Foo".to_string(), description: "About foo".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), create).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTopic { name: "foo".to_string() }).unwrap();
+        let value: TopicResponse = from_binary(&res).unwrap();
+        assert_eq!((Uint128::new(2), Uint128::new(1)), (value.topic.message_count, value.topic.like_count));
+
+        // Taking back what was there before the topic was registered still works
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), ExecuteMsg::UnlikeMessage { id: Uint128::zero() }).unwrap();
+        let edit = ExecuteMsg::EditMessage { id: Uint128::zero(), message: "message1".to_string(), topic: "bar".to_string() };
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), edit).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetTopic { name: "foo".to_string() }).unwrap();
+        let value: TopicResponse = from_binary(&res).unwrap();
+        assert_eq!((Uint128::new(1), Uint128::zero()), (value.topic.message_count, value.topic.like_count));
+    }
+
+    #[test]
+    fn update_config_only_by_admin() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg { admin: Some(SENDER2.to_string()), post_price: Some(vec![coin(LIKECOIN_WRONG_AMOUNT, LIKECOIN_DENOM)]), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER2, &[]), msg.clone()).unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+
+        let bad_price = ExecuteMsg::UpdateConfig(UpdateConfigMsg { like_price: Some(vec![coin(0, LIKECOIN_DENOM)]), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), bad_price).unwrap_err();
+        assert_eq!(ContractError::ValidCoinRequired {}, err);
+        let bad_fee = ExecuteMsg::UpdateConfig(UpdateConfigMsg { fee_bps: Some(10_001), ..Default::default() });
+        let err = execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), bad_fee).unwrap_err();
+        assert_eq!(ContractError::InvalidFeeBps { fee_bps: 10_001 }, err);
+
+        execute(deps.as_mut(), mock_env(), mock_info(SENDER, &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap();
+        let value: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(ConfigResponse { admin: Addr::unchecked(SENDER2), post_price: vec![coin(LIKECOIN_WRONG_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], fee_bps: 0, treasury: None, max_thread_depth: DEFAULT_MAX_THREAD_DEPTH, reactions: vec![], cw20: None, strict_topics: false, topic_creation: TopicCreation::Anyone, topic_price: vec![], auto_hide_reports: 0, rate_limit: None, trending_half_life: DEFAULT_TRENDING_HALF_LIFE, content_rules: ContentRules::default() }, value);
+
+        // The new price applies to posting straight away
+        let msg = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+        let info = mock_info(SENDER, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsMessage { accepted: coin(LIKECOIN_WRONG_AMOUNT, LIKECOIN_DENOM).to_string() }, err);
+        let info = mock_info(SENDER, &[coin(LIKECOIN_WRONG_AMOUNT, LIKECOIN_DENOM)]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn migrate_from_v0_1_layout() {
+        let mut deps = mock_dependencies();
+        // State as written by v0.1.0: no version info, no admin, messages without indexes
+        let message = migrations::v0_1::Message { id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic1".to_string(), message: "message1".to_string() };
+        migrations::v0_1::MESSAGES.save(deps.as_mut().storage, 0, &message).unwrap();
+        migrations::v0_1::STIPEND.save(deps.as_mut().storage, &coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)).unwrap();
+        CURRENT_ID.save(deps.as_mut().storage, &1).unwrap();
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(ContractError::AdminRequired {}, err);
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: Some(SENDER.to_string()) }).unwrap();
+        assert_eq!("0.1.0", res.attributes[1].value);
+        assert_eq!(CONTRACT_VERSION, cw2::get_contract_version(deps.as_ref().storage).unwrap().version);
+
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(Config { admin: Addr::unchecked(SENDER), post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], fee_bps: 0, treasury: None, max_thread_depth: DEFAULT_MAX_THREAD_DEPTH, reactions: vec![], cw20: None, strict_topics: false, topic_creation: TopicCreation::Anyone, topic_price: vec![], auto_hide_reports: 0, rate_limit: None, trending_half_life: DEFAULT_TRENDING_HALF_LIFE, content_rules: ContentRules::default() }, config);
+        assert_eq!(None, migrations::v0_1::STIPEND.may_load(deps.as_ref().storage).unwrap());
+
+        let message = Message { id: Uint128::zero(), owner: Addr::unchecked(SENDER), topic: "topic1".to_string(), message: "message1".to_string(), revision: 0, edited_at: None, deleted: false, parent_id: None, depth: 0, reply_count: Uint128::zero(), hidden: false, created_at: None, created_height: None, tags: vec![] };
+        let msg = QueryMsg::GetMessagesByAddr { address: SENDER.to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![message.clone()], value.messages);
+        let msg = QueryMsg::GetMessagesByTopic { topic: "topic1".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let value: MessagesResponse = from_binary(&query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(vec![message], value.messages);
+        let value: StatsResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetStats {}).unwrap()).unwrap();
+        assert_eq!(Stats { total_messages: Uint128::new(1), live_messages: Uint128::new(1), authors: Uint128::new(1), ..Default::default() }, value.stats);
+    }
+
+    #[test]
+    fn migrate_refuses_foreign_contract_and_downgrade() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cw20-base", "0.13.2").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(ContractError::CannotMigrate { previous_contract: "crates.io:cw20-base".to_string() }, err);
 
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).unwrap_err();
+        assert_eq!(ContractError::CannotMigrateVersion { previous_version: "99.0.0".to_string(), new_version: CONTRACT_VERSION.to_string() }, err);
     }
   
 }
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{OverflowError, StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -6,6 +6,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -15,14 +18,151 @@ pub enum ContractError {
     #[error("Message ID not valid: Not one message instance found")]
     InvalidMessageID{},
 
-    #[error("Invalid funds. {val2:?} {val1:?} must be transferred when creating message")]
-    InvalidFundsMessage{val1: String, val2: String},
+    #[error("Message has been deleted")]
+    MessageDeleted {},
+
+    #[error("Replies cannot be nested deeper than {max_depth} levels")]
+    MaxThreadDepthExceeded { max_depth: u32 },
+
+    #[error("Unknown reaction: {reaction}")]
+    UnknownReaction { reaction: String },
+
+    #[error("Reaction names must be unique and not empty: {reaction:?}")]
+    InvalidReactionName { reaction: String },
+
+    #[error("Invalid funds. {accepted:?} must be transferred when reacting with {reaction}")]
+    InvalidFundsReaction { reaction: String, accepted: String },
+
+    #[error("Address has no sponsored posts left")]
+    NotSponsored {},
+
+    #[error("Topic {topic} is not registered")]
+    UnknownTopic { topic: String },
+
+    #[error("Topic {topic} is already registered")]
+    TopicExists { topic: String },
+
+    #[error("Invalid funds. One of {accepted:?} must be transferred when creating topic")]
+    InvalidFundsTopic { accepted: String },
+
+    #[error("Message has been hidden by moderation")]
+    MessageHidden {},
+
+    #[error("Message already reported by this address")]
+    AlreadyReported {},
+
+    #[error("Address is banned until height {until_height:?}")]
+    Banned { until_height: Option<u64> },
+
+    #[error("Only {limit} {action} allowed every {window_blocks} blocks, try again at height {reset_height}")]
+    RateLimited {
+        action: String,
+        limit: u32,
+        window_blocks: u64,
+        reset_height: u64,
+    },
+
+    #[error("Message already liked by this address")]
+    AlreadyLiked {},
+
+    #[error("Message not liked by this address")]
+    NotLiked {},
+
+    #[error("Invalid funds. One of {accepted:?} must be transferred when creating message")]
+    InvalidFundsMessage { accepted: String },
+
+    #[error("Invalid funds. One of {accepted:?} must be transferred when liking message")]
+    InvalidFundsLike { accepted: String },
+
+    #[error("CW20 token {address} is not accepted")]
+    UnacceptedCw20 { address: String },
+
+    #[error("Fee of {fee_bps} basis points is above 10000")]
+    InvalidFeeBps { fee_bps: u16 },
+
+    #[error("Trending half-life must be at least one block")]
+    InvalidHalfLife {},
+
+    #[error("Trending half-life cannot change once messages have a trending score")]
+    HalfLifeLocked {},
+
+    #[error("Minimum message length is above the maximum, or a maximum is 0")]
+    InvalidContentRules {},
 
-    #[error("Invalid funds. {val2:?} {val1:?} must be transferred when liking message")]
-    InvalidFundsLike{val1: String, val2: String},
+    #[error("Message must be at least {min_length} characters long")]
+    MessageTooShort { min_length: u32 },
+
+    #[error("Message must be at most {max_length} characters long")]
+    MessageTooLong { max_length: u32 },
+
+    #[error("Topic cannot be empty")]
+    EmptyTopic {},
+
+    #[error("Topic must be at most {max_length} characters long")]
+    TopicTooLong { max_length: u32 },
+
+    #[error("Topic title must be at most {max_length} characters long")]
+    TitleTooLong { max_length: u32 },
+
+    #[error("Topic description must be at most {max_length} characters long")]
+    DescriptionTooLong { max_length: u32 },
+
+    #[error("Report reason must be at most {max_length} characters long")]
+    ReasonTooLong { max_length: u32 },
+
+    #[error("Topic cannot contain {character:?}")]
+    InvalidTopicCharacter { character: char },
+
+    #[error("A message can have at most {max_tags} tags")]
+    TooManyTags { max_tags: u32 },
+
+    #[error("Tag {tag} is given more than once")]
+    DuplicateTag { tag: String },
+
+    #[error("Tags cannot be empty")]
+    EmptyTag {},
+
+    #[error("Tags must be at most {max_length} characters long")]
+    TagTooLong { max_length: u32 },
+
+    #[error("Tags cannot contain {character:?}")]
+    InvalidTagCharacter { character: char },
+
+    #[error("A withdrawal needs at least one coin, each of a different denom and above 0")]
+    InvalidWithdrawal {},
+
+    #[error("Only {available} {denom} of fees are available")]
+    InsufficientFees { denom: String, available: Uint128 },
+
+    #[error("Denom {denom} is reserved for CW20 tokens")]
+    ReservedDenom { denom: String },
+
+    #[error("The price lists denom {denom} more than once")]
+    DuplicateDenom { denom: String },
+
+    #[error("An admin must be provided when migrating a contract that has none")]
+    AdminRequired {},
+
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+
+    #[error("Cannot migrate from a different contract: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from newer version {previous_version} to older version {new_version}")]
+    CannotMigrateVersion {
+        previous_version: String,
+        new_version: String,
+    },
 
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
     // Add any other custom errors you like here.
     // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
 }
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
+}
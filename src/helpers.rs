@@ -20,7 +20,7 @@ impl MessagesContract {
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
-            funds: funds,
+            funds,
         }
         .into())
     }
@@ -2,8 +2,11 @@
 mod tests {
     use crate::ContractError;
     use crate::helpers::MessagesContract;
-    use crate::msg::{ExecuteMsg, InstantiateMsg, MessagesResponse, QueryMsg, LikesResponse};
-    use cosmwasm_std::{coin, to_binary, Addr, BankMsg, BankQuery, Coin, Empty, Uint128};
+    use crate::migrations::v0_1;
+    use crate::msg::{ConfigResponse, Cw20PriceMsg, ExecuteMsg, InstantiateMsg, MessagesResponse, MigrateMsg, QueryMsg, ReceiveMsg, TreasuryResponse, UpdateConfigMsg, LikesResponse, ReactionCount, ReactionsResponse, TopicResponse};
+    use crate::state::{ContentRules, Reaction, TopicCreation, CURRENT_ID, DEFAULT_MAX_THREAD_DEPTH, DEFAULT_TRENDING_HALF_LIFE};
+    use cosmwasm_std::{coin, to_binary, Addr, Coin, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128};
+    use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
     use cw_multi_test::{App, AppBuilder, Contract, ContractWrapper, Executor};
 
     pub fn contract_messages() -> Box<dyn Contract<Empty>> {
@@ -11,14 +14,46 @@ mod tests {
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
+        )
+        .with_migrate(crate::contract::migrate);
+        Box::new(contract)
+    }
+
+    // Stand-in for the v0.1.0 code: it stores messages with the v0.1.0 layout.
+    fn instantiate_v0_1(deps: DepsMut, _env: Env, _info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+        CURRENT_ID.save(deps.storage, &0)?;
+        v0_1::STIPEND.save(deps.storage, &msg.post_price[0])?;
+        Ok(Response::default())
+    }
+
+    fn execute_v0_1(deps: DepsMut, _env: Env, info: MessageInfo, msg: ExecuteMsg) -> StdResult<Response> {
+        if let ExecuteMsg::AddMessage { topic, message, .. } = msg {
+            let id = CURRENT_ID.load(deps.storage)?;
+            let message = v0_1::Message { id: Uint128::from(id), owner: info.sender, topic, message };
+            v0_1::MESSAGES.save(deps.storage, id, &message)?;
+            CURRENT_ID.save(deps.storage, &(id + 1))?;
+        }
+        Ok(Response::default())
+    }
+
+    pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            cw20_base::contract::execute,
+            cw20_base::contract::instantiate,
+            cw20_base::contract::query,
         );
         Box::new(contract)
     }
 
+    pub fn contract_messages_v0_1() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(execute_v0_1, instantiate_v0_1, crate::contract::query);
+        Box::new(contract)
+    }
+
     const USER1: &str = "juno10c3slrqx3369mfsr9670au22zvq082jaej8ve4";
     const USER2: &str = "juno1and87527ua866yqh2mpakl9zkxzj5myu6f87ll";
     const ADMIN: &str = "juno1and87527ua866yqh2mpakl9zkxzj5myu6f87ld";
-    const NATIVE_DENOM: &str = "denom";
+    const TREASURY: &str = "juno1treasury";
     const LIKECOIN_DENOM: &str = "like_coin";
     const LIKECOIN_AMOUNT: u128 = 100;
     const LIKECOIN_WRONG_DENOM: &str = "bad_coin";
@@ -63,7 +98,7 @@ mod tests {
     // The implementation can be found on helpers.rs
     fn messages_contract(app: &mut App, code_id: u64) -> MessagesContract {
         // At instantiate the stipend that needs to be sent to add a message or like a message is set up.
-        let msg = InstantiateMsg {stipend: coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)};
+        let msg = InstantiateMsg { post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], ..Default::default() };
           let messages_contract_address = app
             .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "messages", None)
             .unwrap();
@@ -72,7 +107,7 @@ mod tests {
 
     fn get_all_messages(app: &App, messages_contract: &MessagesContract) -> MessagesResponse {
         app.wrap()
-            .query_wasm_smart(messages_contract.addr(), &QueryMsg::GetAllMessage {})
+            .query_wasm_smart(messages_contract.addr(), &QueryMsg::GetAllMessage { start_after: None, limit: None, order: None, include_deleted: None })
             .unwrap()
     }
 
@@ -88,10 +123,23 @@ mod tests {
             .unwrap()
     }
 
+    fn get_messages_by_addr(app: &App, messages_contract: &MessagesContract, address: &str) -> MessagesResponse {
+        app.wrap()
+            .query_wasm_smart(messages_contract.addr(), &QueryMsg::GetMessagesByAddr { address: address.to_string(), start_after: None, limit: None, order: None, include_deleted: None })
+            .unwrap()
+    }
+
     fn get_balance(app: &App, user: String, denom: String) -> Coin {
         app.wrap().query_balance(user, denom).unwrap()
     }
 
+    fn get_cw20_balance(app: &App, token: &Addr, user: &str) -> Uint128 {
+        let balance: BalanceResponse = app.wrap()
+            .query_wasm_smart(token, &Cw20QueryMsg::Balance { address: user.to_string() })
+            .unwrap();
+        balance.balance
+    }
+
     fn print_balances(app: &App, extra_info: &str, contract_address: Addr) {
         let addr = USER1.to_string();
         let denom = LIKECOIN_DENOM.to_string();
@@ -119,9 +167,9 @@ mod tests {
     ) {
         //use ExecuteMsg to add a message
         //use app.execute_contract to send message to contract
-        let msg = ExecuteMsg::AddMessage { topic, message };
+        let msg = ExecuteMsg::AddMessage { topic, message, parent_id: None, tags: None };
         // print_balances(app, "Before Addding Message", messages_contract.addr());
-         let res = app.execute_contract(owner, messages_contract.addr(), &msg, &funds)
+        app.execute_contract(owner, messages_contract.addr(), &msg, &funds)
             .unwrap();
         print_balances(app, "After Addding Message", messages_contract.addr());
     }
@@ -135,13 +183,19 @@ mod tests {
     ) {
         //use ExecuteMsg to add a message
         //use app.execute_contract to send message to contract
-        let msg = ExecuteMsg::AddMessageWithoutFunds { topic, message };
+        let msg = ExecuteMsg::AddMessageWithoutFunds { topic, message, tags: None };
         // print_balances(app, "Before Addding Message", messages_contract.addr());
-         let res = app.execute_contract(owner, messages_contract.addr(), &msg, &[])
+        app.execute_contract(owner, messages_contract.addr(), &msg, &[])
             .unwrap();
         print_balances(app, "After Addding Message without Funds requirements", messages_contract.addr());
     }
 
+    fn grant_sponsored_posts(app: &mut App, messages_contract: &MessagesContract, address: &str, quota: u32) {
+        let msg = ExecuteMsg::GrantSponsoredPosts { address: address.to_string(), quota };
+        app.execute_contract(Addr::unchecked(ADMIN), messages_contract.addr(), &msg, &[])
+            .unwrap();
+    }
+
     fn add_message_wrong_funds(
         app: &mut App,
         messages_contract: &MessagesContract,
@@ -152,7 +206,7 @@ mod tests {
     ) {
         //use ExecuteMsg to add a message
         //use app.execute_contract to send message to contract
-        let msg = ExecuteMsg::AddMessage { topic, message } ;
+        let msg = ExecuteMsg::AddMessage { topic, message, parent_id: None, tags: None };
         print_balances(app, "Before Addding Message with wrong funds", messages_contract.addr());
          let res= app.execute_contract(owner, messages_contract.addr(), &msg, &funds)
             .unwrap_err();
@@ -172,7 +226,7 @@ mod tests {
         //use ExecuteMsg to add a message
         //use app.execute_contract to send message to contract
         let msg = ExecuteMsg::LikeMessage { id: Uint128::from(id) };
-        let res = app.execute_contract(owner, messages_contract.addr(), &msg, &funds)
+        app.execute_contract(owner, messages_contract.addr(), &msg, &funds)
             .unwrap();
         
         print_balances(app, "After Liking Message", messages_contract.addr());
@@ -226,6 +280,7 @@ mod tests {
         let messages_contract = messages_contract(&mut app, code_id);
         let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
         add_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), "topic1".to_string(), "message1".to_string(), funds.clone());
+        grant_sponsored_posts(&mut app, &messages_contract, USER2, 1);
         add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic2".to_string(), "message2".to_string());
         like_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), 1, funds.clone());
  
@@ -233,7 +288,226 @@ mod tests {
         let like_response = get_likes_by_id(&app, &messages_contract,Uint128::from(1u128));
         assert_eq!(message_response.messages.len(),2);
         assert_eq!(like_response.likes.count,Uint128::from(1u128));
+        // The like stipend is relayed to the author of the message
+        let balance = get_balance(&app, USER2.to_string(), LIKECOIN_DENOM.to_string());
+        assert_eq!(balance.amount, Uint128::from(LIKECOIN_AMOUNT));
     }  
 
+    #[test]
+    fn add_messages_from_two_users_and_query_by_addr() {
+        let (mut app, code_id) = store_code();
+        let messages_contract = messages_contract(&mut app, code_id);
+        let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        add_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), "topic1".to_string(), "message1".to_string(), funds.clone());
+        grant_sponsored_posts(&mut app, &messages_contract, USER2, 1);
+        add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic1".to_string(), "message2".to_string());
+        add_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), "topic2".to_string(), "message3".to_string(), funds);
+
+        let message_response = get_messages_by_addr(&app, &messages_contract, USER1);
+        let ids: Vec<Uint128> = message_response.messages.iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec![Uint128::zero(), Uint128::from(2u128)]);
+
+        let message_response = get_messages_by_id(&app, &messages_contract, Uint128::from(1u128));
+        assert_eq!(message_response.messages[0].owner, Addr::unchecked(USER2));
+    }
+
+    #[test]
+    fn upgrade_from_v0_1_keeps_messages_and_sets_admin() {
+        let (mut app, code_id) = store_code();
+        let v0_1_code_id = app.store_code(contract_messages_v0_1());
+        let msg = InstantiateMsg { post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], ..Default::default() };
+        let contract_addr = app
+            .instantiate_contract(v0_1_code_id, Addr::unchecked(ADMIN), &msg, &[], "messages", Some(ADMIN.to_string()))
+            .unwrap();
+        let messages_contract = MessagesContract(contract_addr.clone());
+        let msg = ExecuteMsg::AddMessage { topic: " News".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+        app.execute_contract(Addr::unchecked(USER2), contract_addr.clone(), &msg, &[]).unwrap();
+
+        // v0.1.0 has no admin of its own, so the migration needs one
+        let migrate_msg = MigrateMsg { admin: None };
+        app.migrate_contract(Addr::unchecked(ADMIN), contract_addr.clone(), &migrate_msg, code_id).unwrap_err();
+        let migrate_msg = MigrateMsg { admin: Some(ADMIN.to_string()) };
+        app.migrate_contract(Addr::unchecked(ADMIN), contract_addr.clone(), &migrate_msg, code_id).unwrap();
+
+        let config: ConfigResponse = app.wrap().query_wasm_smart(contract_addr.clone(), &QueryMsg::GetConfig {}).unwrap();
+        assert_eq!(config, ConfigResponse { admin: Addr::unchecked(ADMIN), post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], fee_bps: 0, treasury: None, max_thread_depth: DEFAULT_MAX_THREAD_DEPTH, reactions: vec![], cw20: None, strict_topics: false, topic_creation: TopicCreation::Anyone, topic_price: vec![], auto_hide_reports: 0, rate_limit: None, trending_half_life: DEFAULT_TRENDING_HALF_LIFE, content_rules: ContentRules::default() });
+        let message_response = get_messages_by_addr(&app, &messages_contract, USER2);
+        assert_eq!(message_response.messages.len(), 1);
+        // The legacy topic is normalized like new ones
+        let msg = QueryMsg::GetMessagesByTopic { topic: "NEWS".to_string(), start_after: None, limit: None, order: None, include_deleted: None };
+        let message_response: MessagesResponse = app.wrap().query_wasm_smart(contract_addr.clone(), &msg).unwrap();
+        assert_eq!(message_response.messages[0].topic, "news");
+
+        // The upgraded contract keeps working on the migrated state
+        like_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), 0, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        let like_response = get_likes_by_id(&app, &messages_contract, Uint128::zero());
+        assert_eq!(like_response.likes.count, Uint128::from(1u128));
+        let balance = get_balance(&app, USER2.to_string(), LIKECOIN_DENOM.to_string());
+        assert_eq!(balance.amount, Uint128::from(LIKECOIN_AMOUNT));
+
+        // Registering the legacy topic picks up its counters
+        let msg = ExecuteMsg::CreateTopic { name: "news".to_string(), title: "News".to_string(), description: "".to_string() };
+        app.execute_contract(Addr::unchecked(USER1), contract_addr.clone(), &msg, &[]).unwrap();
+        let topic: TopicResponse = app.wrap().query_wasm_smart(contract_addr, &QueryMsg::GetTopic { name: "news".to_string() }).unwrap();
+        assert_eq!((Uint128::new(1), Uint128::new(1)), (topic.topic.message_count, topic.topic.like_count));
+    }
 
+    #[test]
+    fn upgrade_to_same_version_keeps_state() {
+        let (mut app, code_id) = store_code();
+        let msg = InstantiateMsg { post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)], ..Default::default() };
+        let contract_addr = app
+            .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "messages", Some(ADMIN.to_string()))
+            .unwrap();
+        let messages_contract = MessagesContract(contract_addr.clone());
+        let funds = vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)];
+        add_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), "topic1".to_string(), "message1".to_string(), funds);
+
+        let new_code_id = app.store_code(contract_messages());
+        app.migrate_contract(Addr::unchecked(ADMIN), contract_addr, &MigrateMsg { admin: None }, new_code_id).unwrap();
+
+        let message_response = get_messages_by_addr(&app, &messages_contract, USER1);
+        assert_eq!(message_response.messages.len(), 1);
+    }
+
+    #[test]
+    fn react_to_message_pays_author() {
+        let (mut app, code_id) = store_code();
+        let messages_contract = messages_contract(&mut app, code_id);
+        let reactions = vec![
+            Reaction { name: "laugh".to_string(), price: coin(50, LIKECOIN_DENOM) },
+            Reaction { name: "insightful".to_string(), price: coin(20, LIKECOIN_WRONG_DENOM) },
+        ];
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg { reactions: Some(reactions), ..Default::default() });
+        app.execute_contract(Addr::unchecked(ADMIN), messages_contract.addr(), &msg, &[]).unwrap();
+        grant_sponsored_posts(&mut app, &messages_contract, USER2, 1);
+        add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic1".to_string(), "message1".to_string());
+
+        let react = |reaction: &str| ExecuteMsg::React { id: Uint128::zero(), reaction: reaction.to_string() };
+        let err = app.execute_contract(Addr::unchecked(USER1), messages_contract.addr(), &react("angry"), &[coin(50, LIKECOIN_DENOM)]).unwrap_err();
+        assert_eq!(ContractError::UnknownReaction { reaction: "angry".to_string() }, err.downcast().unwrap());
+        let err = app.execute_contract(Addr::unchecked(USER1), messages_contract.addr(), &react("laugh"), &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsReaction { reaction: "laugh".to_string(), accepted: coin(50, LIKECOIN_DENOM).to_string() }, err.downcast().unwrap());
+
+        app.execute_contract(Addr::unchecked(USER1), messages_contract.addr(), &react("laugh"), &[coin(50, LIKECOIN_DENOM)]).unwrap();
+        app.execute_contract(Addr::unchecked(USER1), messages_contract.addr(), &react("laugh"), &[coin(50, LIKECOIN_DENOM)]).unwrap();
+        app.execute_contract(Addr::unchecked(USER1), messages_contract.addr(), &react("insightful"), &[coin(20, LIKECOIN_WRONG_DENOM)]).unwrap();
+
+        assert_eq!(get_balance(&app, USER2.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(100));
+        assert_eq!(get_balance(&app, USER2.to_string(), LIKECOIN_WRONG_DENOM.to_string()).amount, Uint128::new(20));
+
+        let reactions: ReactionsResponse = app.wrap()
+            .query_wasm_smart(messages_contract.addr(), &QueryMsg::GetReactions { id: Uint128::zero() })
+            .unwrap();
+        assert_eq!(reactions.reactions, vec![
+            ReactionCount { reaction: "insightful".to_string(), count: Uint128::new(1) },
+            ReactionCount { reaction: "laugh".to_string(), count: Uint128::new(2) },
+        ]);
+    }
+
+    #[test]
+    fn like_fee_goes_to_treasury_or_stays_in_contract() {
+        let (mut app, code_id) = store_code();
+        let msg = InstantiateMsg {
+            post_price: vec![coin(50, LIKECOIN_DENOM)],
+            like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)],
+            fee_bps: Some(1_000),
+            treasury: Some(TREASURY.to_string()),
+            ..Default::default()
+        };
+        let contract_addr = app
+            .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "messages", None)
+            .unwrap();
+        let messages_contract = MessagesContract(contract_addr.clone());
+
+        // Posting and liking are priced separately
+        let add = ExecuteMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+        let err = app.execute_contract(Addr::unchecked(USER1), contract_addr.clone(), &add, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsMessage { accepted: "50like_coin".to_string() }, err.downcast().unwrap());
+        add_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), "topic1".to_string(), "message1".to_string(), vec![coin(50, LIKECOIN_DENOM)]);
+        grant_sponsored_posts(&mut app, &messages_contract, USER2, 2);
+        add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic1".to_string(), "message2".to_string());
+        add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic1".to_string(), "message3".to_string());
+
+        let like = ExecuteMsg::LikeMessage { id: Uint128::from(1u128) };
+        let res = app.execute_contract(Addr::unchecked(USER1), contract_addr.clone(), &like, &[coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]).unwrap();
+        let wasm = res.events.iter().find(|e| e.ty == "wasm").unwrap();
+        assert!(wasm.attributes.iter().any(|a| a.key == "author_share" && a.value == "90like_coin"));
+        assert!(wasm.attributes.iter().any(|a| a.key == "fee_share" && a.value == "10like_coin"));
+        assert_eq!(get_balance(&app, USER2.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(90));
+        assert_eq!(get_balance(&app, TREASURY.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(10));
+
+        // Without a treasury the fee stays in the contract, next to the post price
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg { treasury: Some("".to_string()), ..Default::default() });
+        app.execute_contract(Addr::unchecked(ADMIN), contract_addr.clone(), &msg, &[]).unwrap();
+        like_message(&mut app, &messages_contract, Addr::unchecked(USER1.to_string()), 2, vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)]);
+        assert_eq!(get_balance(&app, USER2.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(180));
+        assert_eq!(get_balance(&app, TREASURY.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(10));
+        assert_eq!(get_balance(&app, contract_addr.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(60));
+
+        // The admin can take out what the contract kept
+        let msg = ExecuteMsg::WithdrawFees { to: TREASURY.to_string(), amount: vec![coin(60, LIKECOIN_DENOM)] };
+        app.execute_contract(Addr::unchecked(ADMIN), contract_addr.clone(), &msg, &[]).unwrap();
+        assert_eq!(get_balance(&app, TREASURY.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::new(70));
+        assert_eq!(get_balance(&app, contract_addr.to_string(), LIKECOIN_DENOM.to_string()).amount, Uint128::zero());
+    }
+
+    #[test]
+    fn pay_with_cw20_tokens() {
+        let (mut app, code_id) = store_code();
+        let cw20_id = app.store_code(contract_cw20());
+        let mut new_token = |symbol: &str| {
+            let msg = cw20_base::msg::InstantiateMsg {
+                name: "Community token".to_string(),
+                symbol: symbol.to_string(),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin { address: USER1.to_string(), amount: Uint128::new(1000) }],
+                mint: None,
+                marketing: None,
+            };
+            app.instantiate_contract(cw20_id, Addr::unchecked(ADMIN), &msg, &[], symbol, None).unwrap()
+        };
+        let token = new_token("COMM");
+        let other_token = new_token("OTHER");
+
+        let msg = InstantiateMsg {
+            post_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)],
+            like_price: vec![coin(LIKECOIN_AMOUNT, LIKECOIN_DENOM)],
+            fee_bps: Some(1_000),
+            cw20: Some(Cw20PriceMsg { address: token.to_string(), post_amount: Uint128::new(30), like_amount: Uint128::new(100) }),
+            ..Default::default()
+        };
+        let contract_addr = app
+            .instantiate_contract(code_id, Addr::unchecked(ADMIN), &msg, &[], "messages", None)
+            .unwrap();
+        let messages_contract = MessagesContract(contract_addr.clone());
+        let send = |amount: u128, msg: ReceiveMsg| Cw20ExecuteMsg::Send {
+            contract: contract_addr.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&msg).unwrap(),
+        };
+        let add = ReceiveMsg::AddMessage { topic: "topic1".to_string(), message: "message1".to_string(), parent_id: None, tags: None };
+
+        let err = app.execute_contract(Addr::unchecked(USER1), token.clone(), &send(LIKECOIN_AMOUNT, add.clone()), &[]).unwrap_err();
+        assert_eq!(ContractError::InvalidFundsMessage { accepted: format!("30cw20:{}", token) }, err.downcast().unwrap());
+        let err = app.execute_contract(Addr::unchecked(USER1), other_token.clone(), &send(30, add.clone()), &[]).unwrap_err();
+        assert_eq!(ContractError::UnacceptedCw20 { address: other_token.to_string() }, err.downcast().unwrap());
+        app.execute_contract(Addr::unchecked(USER1), token.clone(), &send(30, add), &[]).unwrap();
+        let message_response = get_messages_by_addr(&app, &messages_contract, USER1);
+        assert_eq!(message_response.messages.len(), 1);
+
+        // The like is paid out to the author in the token, minus the fee
+        grant_sponsored_posts(&mut app, &messages_contract, USER2, 1);
+        add_message_without_funds_requirement(&mut app, &messages_contract, Addr::unchecked(USER2.to_string()), "topic1".to_string(), "message2".to_string());
+        app.execute_contract(Addr::unchecked(USER1), token.clone(), &send(LIKECOIN_AMOUNT, ReceiveMsg::LikeMessage { id: Uint128::from(1u128) }), &[]).unwrap();
+        assert_eq!(get_likes_by_id(&app, &messages_contract, Uint128::from(1u128)).likes.count, Uint128::from(1u128));
+        assert_eq!(get_cw20_balance(&app, &token, USER2), Uint128::new(90));
+
+        let treasury: TreasuryResponse = app.wrap().query_wasm_smart(&contract_addr, &QueryMsg::GetTreasury {}).unwrap();
+        assert_eq!(treasury.available, vec![coin(40, format!("cw20:{}", token))]);
+        let msg = ExecuteMsg::WithdrawFees { to: ADMIN.to_string(), amount: treasury.available };
+        app.execute_contract(Addr::unchecked(ADMIN), contract_addr.clone(), &msg, &[]).unwrap();
+        assert_eq!(get_cw20_balance(&app, &token, ADMIN), Uint128::new(40));
+        assert_eq!(get_cw20_balance(&app, &token, contract_addr.as_str()), Uint128::zero());
+    }
 }
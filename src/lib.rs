@@ -1,7 +1,9 @@
 pub mod contract;
 mod error;
+mod migrations;
 pub mod msg;
 pub mod state;
+mod trending;
 pub mod helpers;
 pub mod integration_tests;
 
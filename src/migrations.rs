@@ -0,0 +1,119 @@
+// Storage migrations between released versions of the contract. Each module knows the
+// layout written by one version and how to reshape it into the current one.
+
+// v0.1.0 was deployed without cw2 versioning. It stored messages in a plain Map, with no
+// owner/topic indexes, and the stipend under its own key, with no admin.
+pub(crate) mod v0_1 {
+    use cosmwasm_std::{Addr, Coin, Order, StdResult, Storage, Uint128};
+    use cw_storage_plus::{Item, Map};
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use crate::contract::normalize_topic;
+    use crate::error::ContractError;
+    use crate::state::{
+        self, messages, rank_likes, update_author_stats, update_topic_counts, Config, ContentRules,
+        Stats, TopicCreation, CONFIG, DEFAULT_MAX_THREAD_DEPTH, DEFAULT_TRENDING_HALF_LIFE,
+        MESSAGES_LIKE, STATS,
+    };
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct Message {
+        pub id: Uint128,
+        pub owner: Addr,
+        pub topic: String,
+        pub message: String,
+    }
+
+    pub const MESSAGES: Map<u128, Message> = Map::new("messages");
+    pub const STIPEND: Item<Coin> = Item::new("stipend_key");
+    // MESSAGES_LIKE is stored as in v0.1.0, `Like { id, count }` under "love_id". Likes
+    // counted by v0.1.0 have no per-liker record, so they cannot be unliked.
+
+    pub fn migrate(storage: &mut dyn Storage, admin: Option<Addr>) -> Result<(), ContractError> {
+        let admin = admin.ok_or(ContractError::AdminRequired {})?;
+        // v0.1.0 charged the same stipend for posting and liking, without fee
+        let stipend = vec![STIPEND.load(storage)?];
+        let config = Config {
+            admin,
+            post_price: stipend.clone(),
+            like_price: stipend,
+            fee_bps: 0,
+            treasury: None,
+            max_thread_depth: DEFAULT_MAX_THREAD_DEPTH,
+            reactions: vec![],
+            cw20: None,
+            strict_topics: false,
+            topic_creation: TopicCreation::Anyone,
+            topic_price: vec![],
+            auto_hide_reports: 0,
+            rate_limit: None,
+            trending_half_life: DEFAULT_TRENDING_HALF_LIFE,
+            content_rules: ContentRules::default(),
+        };
+        CONFIG.save(storage, &config)?;
+        STIPEND.remove(storage);
+
+        // Saving every message through the IndexedMap rewrites it in the current shape
+        // and builds its index entries.
+        let legacy = MESSAGES
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let mut stats = Stats::default();
+        for (id, message) in legacy {
+            let message = state::Message {
+                id: message.id,
+                owner: message.owner,
+                // Topics are normalized since, or they could not be looked up anymore
+                topic: normalize_topic(&message.topic),
+                message: message.message,
+                revision: 0,
+                edited_at: None,
+                deleted: false,
+                parent_id: None,
+                depth: 0,
+                reply_count: Uint128::zero(),
+                hidden: false,
+                created_at: None,
+                created_height: None,
+                tags: vec![],
+            };
+            messages().save(storage, id, &message)?;
+            update_topic_counts(storage, &message.topic, |counts| {
+                counts.message_count += Uint128::new(1);
+                Ok(())
+            })?;
+            update_author_stats(storage, &message.owner, |author| {
+                if author.messages_posted.is_zero() {
+                    stats.authors += Uint128::new(1);
+                }
+                author.messages_posted += Uint128::new(1);
+                Ok(())
+            })?;
+            // Nothing could be deleted yet
+            stats.total_messages += Uint128::new(1);
+            stats.live_messages += Uint128::new(1);
+        }
+
+        // Likes were only counted, so the leaderboards and the likes received start from
+        // those counts. Who liked and what was paid was not kept.
+        let likes = MESSAGES_LIKE
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (id, like) in likes {
+            let message = messages().load(storage, id)?;
+            rank_likes(storage, id, &message.topic, Uint128::zero(), like.count)?;
+            update_topic_counts(storage, &message.topic, |counts| {
+                counts.like_count += like.count;
+                Ok(())
+            })?;
+            update_author_stats(storage, &message.owner, |author| {
+                author.likes_received += like.count;
+                Ok(())
+            })?;
+            stats.total_likes += like.count;
+        }
+        STATS.save(storage, &stats)?;
+        Ok(())
+    }
+}
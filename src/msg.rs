@@ -1,37 +1,383 @@
-use cosmwasm_std::{Uint128, Coin};
+use cosmwasm_std::{Addr, Decimal, Order, Timestamp, Uint128, Coin};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize, };
 
-use crate::state::{Message, Like};
+use crate::state::{AuthorStats, Ban, ContentRules, Cw20Price, Message, Like, RateLimit, Reaction, Stats, Topic, TopicCreation};
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
 pub struct InstantiateMsg { 
-    pub stipend: Coin,
+    // Accepted payments for adding a message, one coin per denom
+    pub post_price: Vec<Coin>,
+    // Accepted payments for liking a message, one coin per denom
+    pub like_price: Vec<Coin>,
+    // Share of every like kept as protocol fee, in basis points. Defaults to 0.
+    pub fee_bps: Option<u16>,
+    // Where the protocol fee is sent. Kept by the contract when not set.
+    pub treasury: Option<String>,
+    // Defaults to the instantiator
+    pub admin: Option<String>,
+    // Deepest reply level allowed. Defaults to 8.
+    pub max_thread_depth: Option<u32>,
+    // Reactions users can add to messages, none by default
+    pub reactions: Option<Vec<Reaction>>,
+    // CW20 token accepted as payment for posts and likes
+    pub cw20: Option<Cw20PriceMsg>,
+    // Only allow registered topics on messages. Defaults to false.
+    pub strict_topics: Option<bool>,
+    // Defaults to anyone
+    pub topic_creation: Option<TopicCreation>,
+    // Price to register a topic, free by default
+    pub topic_price: Option<Vec<Coin>>,
+    // Reports that hide a message automatically. Disabled by default.
+    pub auto_hide_reports: Option<u32>,
+    // No rate limit by default
+    pub rate_limit: Option<RateLimit>,
+    // Blocks for a like to lose half its trending weight. Defaults to 14400.
+    pub trending_half_life: Option<u64>,
+    // Defaults to messages of 1 to 1000 characters and topics of up to 32 lowercase letters,
+    // digits, '-' and '_'
+    pub content_rules: Option<ContentRules>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20PriceMsg {
+    pub address: String,
+    pub post_amount: Uint128,
+    pub like_amount: Uint128,
 }
 
+// Only built once per call, so UpdateConfig being much larger than the rest is fine
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    AddMessage {topic:String, message:String},
-    AddMessageWithoutFunds {topic:String, message:String},
+    AddMessage {
+        topic: String,
+        message: String,
+        // Set to reply to an existing message
+        parent_id: Option<Uint128>,
+        // Extra labels to find the message by, on top of its topic
+        tags: Option<Vec<String>>,
+    },
+    // Only for addresses with sponsored posts left, see GrantSponsoredPosts
+    AddMessageWithoutFunds {
+        topic: String,
+        message: String,
+        tags: Option<Vec<String>>,
+    },
     LikeMessage {id: Uint128},
+    // Replaces the message with a tombstone. Allowed for the owner and the admin.
+    DeleteMessage { id: Uint128 },
+    UnlikeMessage { id: Uint128 },
+    // Pays the reaction price to the author, like LikeMessage does
+    React { id: Uint128, reaction: String },
+    EditMessage {
+        id: Uint128,
+        message: String,
+        topic: String,
+    },
+    UpdateConfig(UpdateConfigMsg),
+    // Flags a message for the moderators. An address can report a message once.
+    ReportMessage { id: Uint128, reason: String },
+    // Admin only
+    UpdateModerators { add: Vec<String>, remove: Vec<String> },
+    // Moderators and admin only. Both take the message out of the moderation queue, and
+    // restoring also dismisses its reports.
+    HideMessage { id: Uint128 },
+    RestoreMessage { id: Uint128 },
+    // Moderators and admin only. Stops the address from posting and liking, until the given
+    // height or for good.
+    Ban {
+        address: String,
+        until_height: Option<u64>,
+    },
+    Unban { address: String },
+    // Registers a topic. Who can do it and its price are set in the config.
+    CreateTopic {
+        name: String,
+        title: String,
+        description: String,
+    },
+    // Admin only. Sets how many more messages the address can post without funds, 0 revokes.
+    GrantSponsoredPosts { address: String, quota: u32 },
+    // Admin only. Sends fees held by the contract.
+    WithdrawFees { to: String, amount: Vec<Coin> },
+    // Payment in the configured CW20 token. The embedded msg is a ReceiveMsg.
+    Receive(Cw20ReceiveMsg),
+}
+
+// Actions that can be paid for with the CW20 token, sent through Cw20ExecuteMsg::Send
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    AddMessage {
+        topic: String,
+        message: String,
+        parent_id: Option<Uint128>,
+        tags: Option<Vec<String>>,
+    },
+    LikeMessage { id: Uint128 },
+}
+
+// Fields left out keep their current value. Only the admin can update the config.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct UpdateConfigMsg {
+    pub admin: Option<String>,
+    pub post_price: Option<Vec<Coin>>,
+    pub like_price: Option<Vec<Coin>>,
+    pub fee_bps: Option<u16>,
+    // An empty string removes the treasury, so fees are kept by the contract
+    pub treasury: Option<String>,
+    pub max_thread_depth: Option<u32>,
+    // Replaces the whole reaction set
+    pub reactions: Option<Vec<Reaction>>,
+    // An empty address stops accepting CW20 payments
+    pub cw20: Option<Cw20PriceMsg>,
+    pub strict_topics: Option<bool>,
+    pub topic_creation: Option<TopicCreation>,
+    // An empty list makes registering topics free
+    pub topic_price: Option<Vec<Coin>>,
+    // 0 disables hiding on reports
+    pub auto_hide_reports: Option<u32>,
+    // A window of 0 blocks removes the rate limit
+    pub rate_limit: Option<RateLimit>,
+    // Stored scores are scaled by the half-life, so it can only change before the first like
+    pub trending_half_life: Option<u64>,
+    // Replaces all the rules. Messages and topics already stored are not checked again.
+    pub content_rules: Option<ContentRules>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    // Admin to set when migrating a contract that was deployed without one
+    pub admin: Option<String>,
+}
+
+// Sort order for paginated queries, by message id. Defaults to ascending.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderBy {
+    Asc,
+    Desc,
+}
+
+impl From<OrderBy> for Order {
+    fn from(order: OrderBy) -> Order {
+        match order {
+            OrderBy::Asc => Order::Ascending,
+            OrderBy::Desc => Order::Descending,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetCurrentId {},
-    GetAllMessage {},
-    GetMessagesByAddr { address:String },
-    GetMessagesByTopic { topic:String },
+    GetAllMessage {
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        // Deleted and hidden messages are left out unless this is true
+        include_deleted: Option<bool>,
+    },
+    GetMessagesByAddr {
+        address: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    GetMessagesByTopic {
+        topic: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    // Messages posted from `start` up to, but not including, `end`, ordered by time
+    GetMessagesBetween {
+        start: Timestamp,
+        end: Timestamp,
+        // Id of the last message of the previous page
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    // Messages with the tag
+    GetMessagesByTag {
+        tag: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    // Messages with all, or any, of the tags
+    GetMessagesByTags {
+        tags: Vec<String>,
+        mode: TagMatch,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    // Tags in use with their message count, by name. Set `prefix` for autocompletion.
+    ListTags {
+        prefix: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Most liked messages, overall or in a topic
+    GetTopMessages {
+        topic: Option<String>,
+        limit: Option<u32>,
+    },
+    // Messages with the most recent likes, each like losing half its weight every half-life
+    GetTrending {
+        topic: Option<String>,
+        limit: Option<u32>,
+    },
     GetMessagesById { id:Uint128 },
     GetLikesById {id:Uint128},
+    // Count of each reaction on a message
+    GetReactions { id: Uint128 },
+    // Direct replies to a message
+    GetReplies {
+        id: Uint128,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    // The message and its replies, depth first, down to the configured max thread depth.
+    // Deleted messages are kept so the thread structure stays intact. Pages continue after
+    // the `start_after` message in the same walk.
+    GetThread {
+        root_id: Uint128,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+    },
+    // Previous versions of a message, oldest first
+    GetMessageHistory {
+        id: Uint128,
+        start_after: Option<u32>,
+        limit: Option<u32>,
+    },
+    // Addresses that liked the message, ordered by address
+    GetLikers {
+        id: Uint128,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Messages liked by the address
+    GetLikedMessages {
+        address: String,
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+        order: Option<OrderBy>,
+        include_deleted: Option<bool>,
+    },
+    GetConfig {},
+    // Reported messages waiting for a moderator, by message id
+    GetModerationQueue {
+        start_after: Option<Uint128>,
+        limit: Option<u32>,
+    },
+    // Reports on a message, ordered by reporter
+    GetReports {
+        id: Uint128,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetModerators {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Banned addresses, ordered by address
+    ListBans {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    GetTopic { name: String },
+    // Registered topics, ordered by name
+    ListTopics {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Posts and likes the address has left in its current rate limit window
+    GetRateLimitQuota { address: String },
+    // Messages the address can still post without funds
+    GetSponsoredQuota { address: String },
+    // Fees held by the contract and collected over its lifetime
+    GetTreasury {},
+    // Totals over the whole board
+    GetStats {},
+    // Activity of an address, all zero when it has none
+    GetAuthorStats { address: String },
+    // Addresses that posted, most likes received first
+    ListTopAuthors {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MessagesResponse {
     pub messages: Vec<Message>,
+    // Id to pass as `start_after` to fetch the next page. None when there are no more messages.
+    pub next_cursor: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    All,
+    Any,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TagsResponse {
+    pub tags: Vec<TagCount>,
+    // Tag to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RankedMessage {
+    pub message: Message,
+    pub likes: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopMessagesResponse {
+    pub messages: Vec<RankedMessage>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TrendingMessage {
+    pub message: Message,
+    // Decayed weight of its likes at the current height
+    pub score: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TrendingResponse {
+    pub messages: Vec<TrendingMessage>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -39,3 +385,162 @@ pub struct MessagesResponse {
 pub struct LikesResponse {
     pub likes: Like,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub admin: Addr,
+    pub post_price: Vec<Coin>,
+    pub like_price: Vec<Coin>,
+    pub fee_bps: u16,
+    pub treasury: Option<Addr>,
+    pub max_thread_depth: u32,
+    pub reactions: Vec<Reaction>,
+    pub cw20: Option<Cw20Price>,
+    pub strict_topics: bool,
+    pub topic_creation: TopicCreation,
+    pub topic_price: Vec<Coin>,
+    pub auto_hide_reports: u32,
+    pub rate_limit: Option<RateLimit>,
+    pub trending_half_life: u64,
+    pub content_rules: ContentRules,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RateLimitQuotaResponse {
+    // None when the action is not limited
+    pub posts_left: Option<u32>,
+    pub likes_left: Option<u32>,
+    // Height at which the address gets its full quota back, if it has used any
+    pub reset_height: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReportedMessage {
+    pub message: Message,
+    pub report_count: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ModerationQueueResponse {
+    pub messages: Vec<ReportedMessage>,
+    // Id to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Report {
+    pub reporter: Addr,
+    pub reason: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReportsResponse {
+    pub reports: Vec<Report>,
+    // Reporter to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ModeratorsResponse {
+    pub moderators: Vec<Addr>,
+    // Address to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BansResponse {
+    pub bans: Vec<Ban>,
+    // Address to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopicResponse {
+    pub topic: Topic,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopicsResponse {
+    pub topics: Vec<Topic>,
+    // Name to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SponsoredQuotaResponse {
+    pub address: Addr,
+    pub remaining: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct StatsResponse {
+    pub stats: Stats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AuthorStatsResponse {
+    pub address: Addr,
+    pub stats: AuthorStats,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TopAuthorsResponse {
+    pub authors: Vec<AuthorStatsResponse>,
+    // Address to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TreasuryResponse {
+    // Where like fees are sent. When not set, they are kept by the contract.
+    pub treasury: Option<Addr>,
+    // Fees that can be withdrawn
+    pub available: Vec<Coin>,
+    // Post payments and like fees kept by the contract since it was deployed
+    pub collected: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct LikersResponse {
+    pub likers: Vec<Addr>,
+    // Address to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MessageHistoryResponse {
+    pub revisions: Vec<Message>,
+    // Revision to pass as `start_after` to fetch the next page
+    pub next_cursor: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReactionCount {
+    pub reaction: String,
+    pub count: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ReactionsResponse {
+    pub id: Uint128,
+    pub reactions: Vec<ReactionCount>,
+}
@@ -1,15 +1,44 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128, Coin};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Uint128, Coin, Empty, StdResult, Storage, Timestamp};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Message {
     pub id:Uint128,
     pub owner:Addr,
     pub topic: String,
-    pub message: String
+    pub message: String,
+    // Fields below were added after messages were first stored, they default when missing.
+    // Number of times the message has been edited
+    #[serde(default)]
+    pub revision: u32,
+    // Block time of the last edit
+    #[serde(default)]
+    pub edited_at: Option<Timestamp>,
+    // Deleted messages keep their id and likes, but their text is cleared
+    #[serde(default)]
+    pub deleted: bool,
+    // Message this one replies to. Top level messages have no parent and depth 0.
+    #[serde(default)]
+    pub parent_id: Option<Uint128>,
+    #[serde(default)]
+    pub depth: u32,
+    #[serde(default)]
+    pub reply_count: Uint128,
+    // Hidden by a moderator, or after enough reports. Left out of listings like tombstones.
+    #[serde(default)]
+    pub hidden: bool,
+    // Block time and height the message was posted at. Unknown for messages posted before
+    // they were recorded.
+    #[serde(default)]
+    pub created_at: Option<Timestamp>,
+    #[serde(default)]
+    pub created_height: Option<u64>,
+    // Normalized like topics, in the order they were given
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -20,10 +49,405 @@ pub struct Like {
 
 pub const CURRENT_ID: Item<u128> = Item::new("current_id");
 
-// Stores the amount the funds required for storing a message or liking a message. Set up at Instantiate
-pub const STIPEND: Item<Coin> = Item::new("stipend_key");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    // Only address allowed to update the config
+    pub admin: Addr,
+    // Accepted payments for storing a message and for liking one. Exactly one of the
+    // coins must be sent.
+    pub post_price: Vec<Coin>,
+    pub like_price: Vec<Coin>,
+    // Share of every like and reaction kept as protocol fee, in basis points
+    pub fee_bps: u16,
+    // Receives the protocol fee. When not set the fee stays in the contract.
+    pub treasury: Option<Addr>,
+    // Deepest reply level allowed, top level messages are at depth 0
+    #[serde(default = "default_max_thread_depth")]
+    pub max_thread_depth: u32,
+    // Reactions available on top of likes, each with the price paid to the author
+    #[serde(default)]
+    pub reactions: Vec<Reaction>,
+    // CW20 token accepted on top of the native prices, paid through the Receive hook
+    #[serde(default)]
+    pub cw20: Option<Cw20Price>,
+    // Messages can only use topics registered with CreateTopic
+    #[serde(default)]
+    pub strict_topics: bool,
+    #[serde(default)]
+    pub topic_creation: TopicCreation,
+    // Accepted payments for registering a topic. Free when empty.
+    #[serde(default)]
+    pub topic_price: Vec<Coin>,
+    // Distinct reporters needed to hide a message until a moderator reviews it. 0 disables it.
+    #[serde(default)]
+    pub auto_hide_reports: u32,
+    // Posts and likes allowed per address in a window of blocks. No limit when not set.
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    // Blocks after which a like counts half as much in the trending score
+    #[serde(default = "default_trending_half_life")]
+    pub trending_half_life: u64,
+    #[serde(default)]
+    pub content_rules: ContentRules,
+}
+
+// Limits on what messages and topics can contain. Lengths are counted in characters.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContentRules {
+    // Leading and trailing whitespace does not count towards the minimum
+    pub min_message_length: u32,
+    pub max_message_length: u32,
+    pub max_topic_length: u32,
+    // Characters topics can be made of, once lowercased. Any character when empty.
+    pub topic_charset: String,
+    // Tags allowed on a message. Each tag follows the same rules as topics.
+    #[serde(default = "default_max_tags")]
+    pub max_tags: u32,
+    // Topic titles. Topic descriptions and report reasons are bounded like messages.
+    #[serde(default = "default_max_title_length")]
+    pub max_title_length: u32,
+}
+
+pub const DEFAULT_MAX_TAGS: u32 = 5;
+
+fn default_max_tags() -> u32 {
+    DEFAULT_MAX_TAGS
+}
+
+pub const DEFAULT_MAX_TITLE_LENGTH: u32 = 100;
+
+fn default_max_title_length() -> u32 {
+    DEFAULT_MAX_TITLE_LENGTH
+}
+
+impl Default for ContentRules {
+    fn default() -> Self {
+        ContentRules {
+            min_message_length: 1,
+            max_message_length: 1000,
+            max_topic_length: 32,
+            topic_charset: "abcdefghijklmnopqrstuvwxyz0123456789-_".to_string(),
+            max_tags: DEFAULT_MAX_TAGS,
+            max_title_length: DEFAULT_MAX_TITLE_LENGTH,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateLimit {
+    pub window_blocks: u64,
+    // 0 leaves the action unlimited
+    pub max_posts: u32,
+    pub max_likes: u32,
+}
+
+// Who can register topics
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TopicCreation {
+    #[default]
+    Anyone,
+    AdminOnly,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20Price {
+    // Token contract
+    pub address: Addr,
+    pub post_amount: Uint128,
+    pub like_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Reaction {
+    pub name: String,
+    pub price: Coin,
+}
+
+pub const DEFAULT_MAX_THREAD_DEPTH: u32 = 8;
+
+fn default_max_thread_depth() -> u32 {
+    DEFAULT_MAX_THREAD_DEPTH
+}
+
+// About a day with 6 second blocks
+pub const DEFAULT_TRENDING_HALF_LIFE: u64 = 14_400;
+
+fn default_trending_half_life() -> u64 {
+    DEFAULT_TRENDING_HALF_LIFE
+}
+
+// Set up at Instantiate, can be changed by the admin with UpdateConfig
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// Secondary indexes over MESSAGES, so queries by owner or topic only touch matching rows.
+pub struct MessageIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, Message, u128>,
+    pub topic: MultiIndex<'a, String, Message, u128>,
+    pub created: MultiIndex<'a, u64, Message, u128>,
+}
+
+impl<'a> IndexList<Message> for MessageIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Message>> + '_> {
+        let v: Vec<&dyn Index<Message>> = vec![&self.owner, &self.topic, &self.created];
+        Box::new(v.into_iter())
+    }
+}
+
+// Messages keyed by id. The primary namespace is the same one the plain Map used, so
+// messages stored before the indexes existed are still readable and only need re-indexing.
+pub fn messages<'a>() -> IndexedMap<'a, u128, Message, MessageIndexes<'a>> {
+    let indexes = MessageIndexes {
+        owner: MultiIndex::new(|m: &Message| m.owner.clone(), "messages", "messages__owner"),
+        topic: MultiIndex::new(|m: &Message| m.topic.clone(), "messages", "messages__topic"),
+        created: MultiIndex::new(created_nanos, "messages", "messages__created"),
+    };
+    IndexedMap::new("messages", indexes)
+}
+
+// Key of the creation time index. Messages without a creation time sort first.
+pub fn created_nanos(message: &Message) -> u64 {
+    message.created_at.map_or(0, |time| time.nanos())
+}
+
+// Replies of every message, keyed by (parent id, reply id).
+pub const REPLIES: Map<(u128, u128), Empty> = Map::new("replies");
+
+// Number of reactions of each kind, keyed by (message id, reaction name).
+pub const REACTION_COUNTS: Map<(u128, &str), Uint128> = Map::new("reaction_counts");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Topic {
+    pub name: String,
+    pub title: String,
+    pub description: String,
+    pub creator: Addr,
+    // Messages in the topic, deleted ones and those from before it was registered included
+    pub message_count: Uint128,
+    // Likes on those messages
+    pub like_count: Uint128,
+}
+
+// Registered topics, by name. Messages on unregistered topics are allowed unless the config
+// is strict.
+pub const TOPICS: Map<&str, Topic> = Map::new("topics");
 
-pub const MESSAGES: Map<u128, Message> = Map::new("messages");
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct TopicCounts {
+    pub message_count: Uint128,
+    pub like_count: Uint128,
+}
+
+// Counters of every topic, registered or not, so registering one only has to copy them.
+pub const TOPIC_COUNTS: Map<&str, TopicCounts> = Map::new("topic_counts");
+
+// Applies `update` to the counters of a topic, and to the topic itself once registered.
+pub fn update_topic_counts(
+    storage: &mut dyn Storage,
+    name: &str,
+    update: impl FnOnce(&mut TopicCounts) -> StdResult<()>,
+) -> StdResult<()> {
+    let mut counts = TOPIC_COUNTS.may_load(storage, name)?.unwrap_or_default();
+    update(&mut counts)?;
+    TOPIC_COUNTS.save(storage, name, &counts)?;
+    if let Some(mut topic) = TOPICS.may_load(storage, name)? {
+        topic.message_count = counts.message_count;
+        topic.like_count = counts.like_count;
+        TOPICS.save(storage, name, &topic)?;
+    }
+    Ok(())
+}
+
+// Addresses allowed to hide and restore messages, on top of the admin.
+pub const MODERATORS: Map<&Addr, Empty> = Map::new("moderators");
+
+// Reason given by each reporter, keyed by (message id, reporter).
+pub const REPORTS: Map<(u128, &Addr), String> = Map::new("reports");
+
+// Reported messages waiting for a moderator, with their number of reports.
+pub const MODERATION_QUEUE: Map<u128, u32> = Map::new("moderation_queue");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Ban {
+    pub address: Addr,
+    // The ban is over at this height. Never over when not set.
+    pub until_height: Option<u64>,
+    pub banned_by: Addr,
+}
+
+// Addresses that can't post or like, set by moderators.
+pub const BANS: Map<&Addr, Ban> = Map::new("bans");
+
+// Posts and likes of an address in its current window, which starts with its first
+// action after the previous window ended.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RateWindow {
+    pub start_height: u64,
+    pub posts: u32,
+    pub likes: u32,
+}
+
+pub const RATE_WINDOWS: Map<&Addr, RateWindow> = Map::new("rate_windows");
+
+// Free posts left for addresses the admin sponsors. Addresses without quota are removed.
+pub const SPONSORED_POSTS: Map<&Addr, u32> = Map::new("sponsored_posts");
+
+// Fees held by the contract and not withdrawn yet, by denom. CW20 fees are booked under
+// `cw20:<token address>`.
+pub const FEES: Map<&str, Uint128> = Map::new("fees");
+
+// Every fee the contract has kept, by denom. Withdrawals don't lower it, refunds do.
+pub const FEES_COLLECTED: Map<&str, Uint128> = Map::new("fees_collected");
+
+// Previous versions of edited messages, keyed by (message id, revision).
+pub const MESSAGE_HISTORY: Map<(u128, u32), Message> = Map::new("message_history");
 
 // Records how which message_id have been liked and how many likes.
 pub const MESSAGES_LIKE: Map<u128, Like> = Map::new("love_id");
+
+// Messages by tag, for the tag queries
+pub const TAGGED: Map<(&str, u128), Empty> = Map::new("tagged");
+
+// Messages using each tag, deleted ones left out. Tags no message uses are removed.
+pub const TAG_COUNTS: Map<&str, u32> = Map::new("tag_counts");
+
+// Leaderboards of liked messages, keyed by (like count, message id), overall and per topic.
+// Messages without likes are left out.
+pub const LIKE_RANKING: Map<(u128, u128), Empty> = Map::new("like_ranking");
+pub const TOPIC_LIKE_RANKING: Map<(&str, u128, u128), Empty> = Map::new("topic_like_ranking");
+
+// Moves a message from its old like count to the new one in both leaderboards.
+pub fn rank_likes(
+    storage: &mut dyn Storage,
+    id: u128,
+    topic: &str,
+    old: Uint128,
+    new: Uint128,
+) -> StdResult<()> {
+    if !old.is_zero() {
+        LIKE_RANKING.remove(storage, (old.u128(), id));
+        TOPIC_LIKE_RANKING.remove(storage, (topic, old.u128(), id));
+    }
+    if !new.is_zero() {
+        LIKE_RANKING.save(storage, (new.u128(), id), &Empty {})?;
+        TOPIC_LIKE_RANKING.save(storage, (topic, new.u128(), id), &Empty {})?;
+    }
+    Ok(())
+}
+
+// Totals over the whole board
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Stats {
+    pub total_messages: Uint128,
+    // Messages that were not deleted
+    pub live_messages: Uint128,
+    pub total_likes: Uint128,
+    // Addresses that posted at least once
+    pub authors: Uint128,
+    // Everything paid to the contract, one coin per denom
+    pub payment_volume: Vec<Coin>,
+}
+
+pub const STATS: Item<Stats> = Item::new("stats");
+
+// Activity of an address on the board
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct AuthorStats {
+    pub messages_posted: Uint128,
+    pub likes_received: Uint128,
+    pub likes_given: Uint128,
+    // Author share of the likes and reactions on its messages, one coin per denom
+    pub earned: Vec<Coin>,
+    // Paid to the contract for posts, likes, reactions and topics, one coin per denom
+    pub spent: Vec<Coin>,
+}
+
+pub const AUTHOR_STATS: Map<&Addr, AuthorStats> = Map::new("author_stats");
+
+// Addresses that posted, by likes received
+pub const AUTHOR_RANKING: Map<(u128, &Addr), Empty> = Map::new("author_ranking");
+
+// Applies `update` to the stats of an address and keeps its rank among authors in step.
+pub fn update_author_stats(
+    storage: &mut dyn Storage,
+    address: &Addr,
+    update: impl FnOnce(&mut AuthorStats) -> StdResult<()>,
+) -> StdResult<()> {
+    let mut stats = AUTHOR_STATS.may_load(storage, address)?.unwrap_or_default();
+    if !stats.messages_posted.is_zero() {
+        AUTHOR_RANKING.remove(storage, (stats.likes_received.u128(), address));
+    }
+    update(&mut stats)?;
+    if !stats.messages_posted.is_zero() {
+        AUTHOR_RANKING.save(storage, (stats.likes_received.u128(), address), &Empty {})?;
+    }
+    AUTHOR_STATS.save(storage, address, &stats)
+}
+
+// Trending score of liked messages, as kept by the trending module
+pub const TRENDING: Map<u128, u128> = Map::new("trending");
+
+// Messages by trending score, overall and per topic
+pub const TRENDING_RANKING: Map<(u128, u128), Empty> = Map::new("trending_ranking");
+pub const TOPIC_TRENDING_RANKING: Map<(&str, u128, u128), Empty> =
+    Map::new("topic_trending_ranking");
+
+// Stores the new trending score of a message and moves it in both rankings. None removes it.
+pub fn rank_trending(
+    storage: &mut dyn Storage,
+    id: u128,
+    topic: &str,
+    score: Option<u128>,
+) -> StdResult<()> {
+    if let Some(old) = TRENDING.may_load(storage, id)? {
+        TRENDING_RANKING.remove(storage, (old, id));
+        TOPIC_TRENDING_RANKING.remove(storage, (topic, old, id));
+    }
+    match score {
+        Some(score) => {
+            TRENDING.save(storage, id, &score)?;
+            TRENDING_RANKING.save(storage, (score, id), &Empty {})?;
+            TOPIC_TRENDING_RANKING.save(storage, (topic, score, id), &Empty {})
+        }
+        None => {
+            TRENDING.remove(storage, id);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MessageLike {
+    pub message_id: Uint128,
+    pub liker: Addr,
+    // Not known for likes given before the trending score was kept
+    #[serde(default)]
+    pub height: Option<u64>,
+    // Fee share the contract kept from the payment, given back on unlike while it still holds it
+    #[serde(default)]
+    pub kept_fee: Option<Coin>,
+}
+
+pub struct MessageLikeIndexes<'a> {
+    pub liker: MultiIndex<'a, Addr, MessageLike, (u128, Addr)>,
+}
+
+impl<'a> IndexList<MessageLike> for MessageLikeIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MessageLike>> + '_> {
+        let v: Vec<&dyn Index<MessageLike>> = vec![&self.liker];
+        Box::new(v.into_iter())
+    }
+}
+
+// One record per (message id, liker), so an address can only like a message once.
+// MESSAGES_LIKE keeps the aggregated count.
+pub fn message_likes<'a>() -> IndexedMap<'a, (u128, &'a Addr), MessageLike, MessageLikeIndexes<'a>> {
+    let indexes = MessageLikeIndexes {
+        liker: MultiIndex::new(
+            |l: &MessageLike| l.liker.clone(),
+            "message_likes",
+            "message_likes__liker",
+        ),
+    };
+    IndexedMap::new("message_likes", indexes)
+}
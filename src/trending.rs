@@ -0,0 +1,114 @@
+// Fixed-point math behind the trending score.
+//
+// A like at height h weighs 2^((h - now) / half_life), so it is worth 1 when given and half as
+// much every half-life after that. Instead of the score itself, a message keeps the log2 of the
+// sum of 2^(h / half_life) over its likes, scaled by the half-life so it reads in blocks. The
+// score at any height follows from it, and since every message decays at the same pace the
+// stored values already rank messages without being refreshed as blocks go by.
+
+use cosmwasm_std::Decimal;
+
+// Fractional bits of the fixed-point numbers
+const FRAC_BITS: u32 = 32;
+const ONE: u128 = 1 << FRAC_BITS;
+
+// Weights more than this many half-lives apart are not worth adding up
+const MAX_SPREAD: u128 = 64 * ONE;
+
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = 1 << ((128 - value.leading_zeros()) / 2 + 1);
+    loop {
+        let y = (x + value / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+// 2^x for a fixed-point exponent, as a fixed-point value
+fn exp2(x: i128) -> u128 {
+    let int = x >> FRAC_BITS;
+    let frac = (x & (ONE as i128 - 1)) as u128;
+    // 2^frac, multiplying in 2^(1/2), 2^(1/4), ... for every bit set
+    let mut value = ONE;
+    let mut root = 2 * ONE;
+    for bit in (0..FRAC_BITS).rev() {
+        root = isqrt(root << FRAC_BITS);
+        if frac & (1 << bit) != 0 {
+            value = (value * root) >> FRAC_BITS;
+        }
+    }
+    match int {
+        int if int >= 0 => value << int.min(64),
+        int if int > -128 => value >> -int,
+        _ => 0,
+    }
+}
+
+// log2 of a positive fixed-point value, as a fixed-point value
+fn log2(value: u128) -> i128 {
+    let int = (127 - value.leading_zeros()) as i128 - FRAC_BITS as i128;
+    // Bring the value to [1, 2), then square it bit by bit for the fraction
+    let mut y = if int >= 0 {
+        value >> int
+    } else {
+        value << -int
+    };
+    let mut result = int << FRAC_BITS;
+    for bit in (0..FRAC_BITS).rev() {
+        y = (y * y) >> FRAC_BITS;
+        if y >= 2 * ONE {
+            y >>= 1;
+            result += 1 << bit;
+        }
+    }
+    result
+}
+
+// Adds a like given at `height` to a stored score
+pub fn add_like(score: Option<u128>, height: u64, half_life: u64) -> u128 {
+    let like = (height as u128) << FRAC_BITS;
+    let score = match score {
+        Some(score) => score,
+        None => return like,
+    };
+    let (high, low) = if score >= like {
+        (score, like)
+    } else {
+        (like, score)
+    };
+    let spread = (high - low) / half_life as u128;
+    if spread >= MAX_SPREAD {
+        return high;
+    }
+    let sum = ONE + exp2(-(spread as i128));
+    high + half_life as u128 * log2(sum) as u128
+}
+
+// Takes a like given at `height` out of a stored score, None when nothing is left of it
+pub fn remove_like(score: u128, height: u64, half_life: u64) -> Option<u128> {
+    let like = (height as u128) << FRAC_BITS;
+    if like >= score {
+        return None;
+    }
+    let spread = (score - like) / half_life as u128;
+    if spread >= MAX_SPREAD {
+        return Some(score);
+    }
+    let rest = ONE
+        .checked_sub(exp2(-(spread as i128)))
+        .filter(|rest| *rest > 0)?;
+    let score = score as i128 + half_life as i128 * log2(rest);
+    (score > 0).then_some(score as u128)
+}
+
+// Sum of the decayed weights of the likes at `height`
+pub fn current_score(score: u128, height: u64, half_life: u64) -> Decimal {
+    let now = (height as u128) << FRAC_BITS;
+    let exponent = (score as i128 - now as i128) / half_life as i128;
+    Decimal::from_ratio(exp2(exponent), ONE)
+}